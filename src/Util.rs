@@ -20,4 +20,20 @@ pub fn textOut<'a>(
     let rendered_text = texture_creator.create_texture_from_surface(&surface).ok().expect("failed to render text");
     let l = text.len();
     canvas.copy(&rendered_text, None, Some(Rect::new(x, y, w*(l as u32), h))).expect("failed to copy text");
-}
\ No newline at end of file
+}
+
+// 矩形rectに内接する楕円を塗りつぶす
+pub fn fillEllipse(canvas: &mut Canvas<Window>, rect: Rect, r: u8, g: u8, b: u8) {
+    let rx = (rect.width() as f64) / 2.0;
+    let ry = (rect.height() as f64) / 2.0;
+    let cx = (rect.x() as f64) + rx;
+
+    canvas.set_draw_color(Color::RGB(r, g, b));
+    for iy in 0..(rect.height() as i32) {
+        // 各行の中心からの距離で横幅を決める
+        let dy = ((iy as f64) + 0.5 - ry) / ry;
+        let hw = rx * (1.0 - dy * dy).max(0.0).sqrt();
+        let y = rect.y() + iy;
+        canvas.draw_line(((cx - hw) as i32, y), ((cx + hw) as i32, y)).expect("failed to draw line");
+    }
+}
@@ -0,0 +1,188 @@
+#![allow(non_snake_case)]
+
+use crate::board::{Piece, Pos};
+use crate::record::{pieceToName, GameRecord};
+
+// 1手の解析結果
+#[derive(Clone, Debug, PartialEq)]
+pub struct MoveAnalysis {
+    pub moveNo: usize,      // 手数（1から）
+    pub piece: Piece,       // 打った側
+    pub played: Pos,        // 打たれた手
+    pub best: Pos,          // エンジンの最善手
+    pub bestValue: i32,     // 最善手の評価値
+    pub playedValue: i32,   // 打たれた手の評価値
+    pub loss: i32,          // bestValue - playedValue
+    pub bBlunder: bool      // lossがしきい値を超えた（悪手）
+}
+
+// 棋譜の各局面をdepth手先まで読み、打たれた手を評価する
+//
+// パスは解析しない．lossがthresholdを超えた手を悪手とする．
+pub fn analyzeGame(gameRecord: &GameRecord, depth: i32, threshold: i32) -> Result<Vec<MoveAnalysis>, String> {
+    let steps = gameRecord.replay()?;
+    let mut results = vec!();
+
+    for (i, m) in gameRecord.moves.iter().enumerate() {
+        let played = match m.pos {
+            Some(pos) => pos,
+            None => {
+                continue;
+            }
+        };
+
+        let values = steps[i].board.getMoveValues(&m.piece, depth);
+        let (best, bestValue) = match values.iter().max_by_key(|&elem| elem.1) {
+            Some(v) => *v,
+            None => {
+                continue;
+            }
+        };
+        let playedValue = match values.iter().find(|v| v.0 == played) {
+            Some(v) => v.1,
+            None => {
+                return Err(format!("move {} ({}) is illegal", i + 1, Pos::toDesc(played.x, played.y)));
+            }
+        };
+        let loss = bestValue - playedValue;
+
+        results.push(MoveAnalysis {
+            moveNo: i + 1,
+            piece: m.piece,
+            played,
+            best,
+            bestValue,
+            playedValue,
+            loss,
+            bBlunder: loss > threshold
+        });
+    }
+
+    return Ok(results);
+}
+
+// 解析結果を表にする
+pub fn formatAnalysis(analyses: &Vec<MoveAnalysis>) -> String {
+    let mut text = String::from("  #  side   played  value   best  value   loss\n");
+    for a in analyses {
+        text.push_str(&format!(
+            "{:>3}  {:<5}  {:>6} {:>6} {:>6} {:>6} {:>6}{}\n",
+            a.moveNo,
+            pieceToName(&a.piece),
+            Pos::toDesc(a.played.x, a.played.y),
+            a.playedValue,
+            Pos::toDesc(a.best.x, a.best.y),
+            a.bestValue,
+            a.loss,
+            if a.bBlunder { "  ??" } else { "" }
+        ));
+    }
+
+    for piece in &[Piece::Black, Piece::White] {
+        let mine = analyses.iter().filter(|a| a.piece == *piece).collect::<Vec<&MoveAnalysis>>();
+        let totalLoss: i32 = mine.iter().map(|a| a.loss).sum();
+        let nBlunders = mine.iter().filter(|a| a.bBlunder).count();
+        text.push_str(&format!("{}: {} moves, total loss {}, {} blunders\n", pieceToName(piece), mine.len(), totalLoss, nBlunders));
+    }
+    return text;
+}
+
+// 解析結果を注釈として付けた棋譜ファイルの内容を返す
+//
+// 注釈は[Moves]の後ろの[Analysis]に書くので、GameRecord::parseでそのまま読める．
+pub fn formatAnnotated(gameRecord: &GameRecord, analyses: &Vec<MoveAnalysis>) -> String {
+    let mut text = gameRecord.format();
+    text.push_str("[Analysis]\n");
+    for a in analyses {
+        text.push_str(&format!(
+            "{} {} {} value={} best={} bestValue={} loss={}{}\n",
+            a.moveNo,
+            pieceToName(&a.piece),
+            Pos::toDesc(a.played.x, a.played.y),
+            a.playedValue,
+            Pos::toDesc(a.best.x, a.best.y),
+            a.bestValue,
+            a.loss,
+            if a.bBlunder { " blunder" } else { "" }
+        ));
+    }
+    return text;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::Board;
+
+    fn createRecord(moves: &[&str]) -> GameRecord {
+        let mut board = Board::new();
+        board.init();
+        let mut record = GameRecord::new(&board, &Piece::Black);
+        let mut piece = Piece::Black;
+        for desc in moves {
+            record.push(&piece, Pos::fromDesc(desc));
+            piece = Piece::getOpponent(&piece);
+        }
+        return record;
+    }
+
+    #[test]
+    fn analyzeShortGame() {
+        let record = createRecord(&["F5", "D6", "C3", "D3", "C4", "F4"]);
+        let analyses = analyzeGame(&record, 2, 3).unwrap();
+        assert_eq!(analyses.len(), 6);
+
+        // 初手はどこも同じ評価なので損はない
+        assert_eq!(analyses[0].moveNo, 1);
+        assert_eq!(analyses[0].piece, Piece::Black);
+        assert_eq!(analyses[0].loss, 0);
+        assert_eq!(analyses[0].playedValue, analyses[0].bestValue);
+
+        // 3手目C3はC6より3悪いが、しきい値ちょうどなので悪手ではない
+        let third = &analyses[2];
+        assert_eq!((third.played, third.best), (Pos {x: 3, y: 3}, Pos {x: 3, y: 6}));
+        assert_eq!((third.playedValue, third.bestValue, third.loss), (-2, 1, 3));
+        assert!(!third.bBlunder);
+
+        // 6手目F4はB3より15悪い悪手
+        let sixth = &analyses[5];
+        assert_eq!(sixth.piece, Piece::White);
+        assert_eq!((sixth.played, sixth.best), (Pos {x: 6, y: 4}, Pos {x: 2, y: 3}));
+        assert_eq!((sixth.playedValue, sixth.bestValue, sixth.loss), (-21, -6, 15));
+        assert!(sixth.bBlunder);
+
+        assert_eq!(analyses.iter().filter(|a| a.bBlunder).count(), 1);
+        assert!(analyses.iter().all(|a| a.loss == a.bestValue - a.playedValue));
+    }
+
+    #[test]
+    fn analyzeSkipsPassAndRejectsIllegalMove() {
+        let mut record = createRecord(&["F5"]);
+        record.push(&Piece::White, None);
+        let analyses = analyzeGame(&record, 1, 3).unwrap();
+        assert_eq!(analyses.len(), 1);
+
+        let record = createRecord(&["F5", "A1"]);
+        assert_eq!(analyzeGame(&record, 1, 3), Err("move 2 (A1) is illegal".to_string()));
+    }
+
+    #[test]
+    fn formatShortGame() {
+        let record = createRecord(&["F5", "D6", "C3", "D3", "C4", "F4"]);
+        let analyses = analyzeGame(&record, 2, 3).unwrap();
+
+        let table = formatAnalysis(&analyses);
+        assert!(table.contains("  3  Black      C3     -2     C6      1      3\n"));
+        assert!(table.contains("  6  White      F4    -21     B3     -6     15  ??\n"));
+        assert!(table.ends_with("Black: 3 moves, total loss 3, 0 blunders\nWhite: 3 moves, total loss 18, 1 blunders\n"));
+
+        let annotated = formatAnnotated(&record, &analyses);
+        assert!(annotated.starts_with(&record.format()));
+        assert!(annotated.contains("[Analysis]\n1 Black F5 value=2 best=E6 bestValue=2 loss=0\n"));
+        assert!(annotated.contains("3 Black C3 value=-2 best=C6 bestValue=1 loss=3\n"));
+        assert!(annotated.ends_with("6 White F4 value=-21 best=B3 bestValue=-6 loss=15 blunder\n"));
+
+        // 注釈を付けても棋譜として読める
+        assert_eq!(GameRecord::parse(&annotated), Ok(record));
+    }
+}
@@ -0,0 +1,753 @@
+#![allow(non_snake_case)]
+
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::sync::mpsc::channel;
+use std::thread;
+use std::time::Duration;
+
+use crate::board::{isValidSize, Board, Count, Piece, Pos, DEFAULT_SIZE};
+use crate::level::findLevel;
+use crate::mcts::{MctsLimit, Playout};
+use crate::player::{Player, Move, Evaluation, Randomness, RandomPlayer, GreedyPlayer, EnginePlayer, MctsPlayer};
+use crate::random::Rng;
+use crate::record::GameRecord;
+
+// 対局に使う互角の序盤（8x8の盤で黒から交互に打つ）
+pub const OPENINGS: [&str; 8] = [
+    "F5 D6 C3 D3",
+    "F5 D6 C5 F4",
+    "F5 D6 C4 D3",
+    "F5 F6 E6 F4",
+    "F5 F6 E6 D6",
+    "F5 F4 E3 F6",
+    "F5 F4 E3 D6",
+    "F5 D6 C6 F4"
+];
+
+// 文字列で指定されたプレイヤーを作る（人間は除く）
+//
+// 「種類[:設定,...]」の形式．設定は「key=value」か、数字だけなら主な値を表す．
+//
+// (ex)
+// random
+// greedy
+// engine:9                 深さ9
+// engine:depth=13,time=500 深さ13まで、1手500ミリ秒
+// engine:eval=disc         駒の数だけで評価する
+// engine:weights=w.txt     スコア計算用の係数をファイルから読む
+// engine:random=tie,seed=1 同点の手からランダムに選ぶ（seedを指定すると毎回同じ）
+// engine:temperature=20    スコアの差に応じた確率で選ぶ
+// random:seed=1
+// mcts:20000               20000回プレイアウト
+// mcts:time=1000,playout=guided
+// level:easy               名前の付いた強さ（level::LEVELS）
+// level:beginner,seed=1    わざと間違える手もseedで決める
+pub fn createPlayer(spec: &str) -> Result<Box<dyn Player>, String> {
+    let mut it = spec.splitn(2, ':');
+    let kind = it.next().unwrap_or("");
+    let mut options = vec!();
+    if let Some(s) = it.next() {
+        for option in s.split(',') {
+            let mut kv = option.splitn(2, '=');
+            let key = kv.next().unwrap_or("").trim();
+            match kv.next() {
+                Some(value) => options.push((key.to_string(), value.trim().to_string())),
+                None => options.push(("".to_string(), key.to_string()))
+            }
+        }
+    }
+
+    let player: Box<dyn Player> = match kind {
+        "random" => {
+            let mut seed = Rng::fromTime().nextU64();
+            for (key, value) in &options {
+                match key.as_str() {
+                    "seed" => seed = parseSeed(spec, value)?,
+                    _ => {
+                        return Err(format!("{}: unknown option '{}'", spec, key));
+                    }
+                }
+            }
+            Box::new(RandomPlayer::new(seed))
+        },
+        "greedy" => Box::new(GreedyPlayer),
+        "level" => {
+            let mut name = "";
+            let mut optSeed = None;
+            for (key, value) in &options {
+                match key.as_str() {
+                    "" | "name" => name = value,
+                    "seed" => optSeed = Some(parseSeed(spec, value)?),
+                    _ => {
+                        return Err(format!("{}: unknown option '{}'", spec, key));
+                    }
+                }
+            }
+            let level = findLevel(name).ok_or(format!("{}: unknown level '{}'", spec, name))?;
+            match optSeed {
+                Some(seed) => level.createPlayerWithSeed(seed),
+                None => level.createPlayer()
+            }
+        },
+        "engine" => {
+            let mut player = EnginePlayer::new(13);
+            for (key, value) in &options {
+                match key.as_str() {
+                    "" | "depth" => {
+                        // 深さ0だと置ける場所があっても手を返さない
+                        let depth = parseNumber(spec, value)?;
+                        if depth < 1 {
+                            return Err(format!("{}: depth must be at least 1", spec));
+                        }
+                        player.depth = depth as i32;
+                    },
+                    "time" => player.optTime = Some(Duration::from_millis(parseNumber(spec, value)? as u64)),
+                    "eval" => {
+                        player.evaluation = match value.as_str() {
+                            "positional" => Evaluation::Positional,
+                            "disc" => Evaluation::Disc,
+                            _ => {
+                                return Err(format!("{}: unknown evaluator '{}'", spec, value));
+                            }
+                        };
+                    },
+                    "weights" => player.evaluation = Evaluation::Weights(loadWeights(value)?),
+                    "random" => {
+                        player.randomness = match value.as_str() {
+                            "none" => Randomness::None,
+                            "tie" => Randomness::TieBreak,
+                            _ => {
+                                return Err(format!("{}: unknown randomness '{}'", spec, value));
+                            }
+                        };
+                    },
+                    "temperature" => {
+                        let temperature = value.parse::<f64>().map_err(|_| format!("{}: invalid temperature '{}'", spec, value))?;
+                        player.randomness = Randomness::Softmax(temperature);
+                    },
+                    "seed" => player.setSeed(parseSeed(spec, value)?),
+                    _ => {
+                        return Err(format!("{}: unknown option '{}'", spec, key));
+                    }
+                }
+            }
+            Box::new(player)
+        },
+        "mcts" => {
+            let mut player = MctsPlayer::new(MctsLimit::Iterations(20000));
+            for (key, value) in &options {
+                match key.as_str() {
+                    "" | "iterations" => player.engine.limit = MctsLimit::Iterations(parseNumber(spec, value)?),
+                    "time" => player.engine.limit = MctsLimit::Time(Duration::from_millis(parseNumber(spec, value)? as u64)),
+                    "playout" => {
+                        player.engine.playout = match value.as_str() {
+                            "random" => Playout::Random,
+                            "guided" => Playout::Guided,
+                            _ => {
+                                return Err(format!("{}: unknown playout '{}'", spec, value));
+                            }
+                        };
+                    },
+                    "seed" => player.engine.setSeed(parseSeed(spec, value)?),
+                    _ => {
+                        return Err(format!("{}: unknown option '{}'", spec, key));
+                    }
+                }
+            }
+            Box::new(player)
+        },
+        _ => {
+            return Err(format!("unknown player '{}'", spec));
+        }
+    };
+    return Ok(player);
+}
+
+fn parseNumber(spec: &str, value: &str) -> Result<u32, String> {
+    return value.parse::<u32>().map_err(|_| format!("{}: invalid number '{}'", spec, value));
+}
+
+fn parseSeed(spec: &str, value: &str) -> Result<u64, String> {
+    return value.parse::<u64>().map_err(|_| format!("{}: invalid seed '{}'", spec, value));
+}
+
+// スコア計算用の係数をファイルから読む
+//
+// 左上から行ごとに盤のマスの数（8x8なら64個）の整数を空白かカンマで区切って書く．
+// #以降はコメント．
+pub fn loadWeights(path: &str) -> Result<Vec<i32>, String> {
+    let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+    let mut weights = vec!();
+    for rawLine in text.lines() {
+        let line = match rawLine.find('#') {
+            Some(n) => &rawLine[..n],
+            None => rawLine
+        };
+        for token in line.split(|c: char| c.is_whitespace() || c == ',').filter(|t| t.len() > 0) {
+            match token.parse::<i32>() {
+                Ok(n) => weights.push(n),
+                Err(_) => {
+                    return Err(format!("{}: invalid weight '{}'", path, token));
+                }
+            }
+        }
+    }
+    let size = (weights.len() as f64).sqrt().round() as i32;
+    if ((size * size) as usize != weights.len()) || !isValidSize(size) {
+        return Err(format!("{}: expected one weight per square (64 for 8x8), found {}", path, weights.len()));
+    }
+    return Ok(weights);
+}
+
+// "F5 D6 C3"のような手順を位置のリストにする
+pub fn parseMoves(moves: &str) -> Result<Vec<Pos>, String> {
+    let mut result = vec!();
+    for token in moves.split_whitespace() {
+        match Pos::fromDesc(token) {
+            Some(pos) => result.push(pos),
+            None => {
+                return Err(format!("invalid move '{}'", token));
+            }
+        }
+    }
+    return Ok(result);
+}
+
+// 1局の結果
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameOutcome {
+    pub record: GameRecord, // 序盤を含む棋譜
+    pub count: Count        // 終局時の駒の数
+}
+
+impl GameOutcome {
+    // 黒から見た石差
+    pub fn getMargin(&self) -> i32 {
+        return self.count.nBlackPieces - self.count.nWhitePieces;
+    }
+}
+
+// i番目の序盤（OPENINGSの添字）を一辺sizeマスの盤用に返す
+//
+// OPENINGSは8x8用なので、ほかの大きさではgenOpeningsで作った序盤を使う．
+pub fn getOpening(size: i32, i: usize) -> Result<Vec<Pos>, String> {
+    if size != DEFAULT_SIZE {
+        let openings = genOpenings(size);
+        return Ok(if openings.len() > 0 { openings[i % openings.len()].clone() } else { vec!() });
+    }
+    return parseMoves(OPENINGS[i % OPENINGS.len()]);
+}
+
+// 一辺sizeマスの盤の初期配置から黒と白が1手ずつ打つ序盤をすべて返す
+//
+// 決まった手を打つエンジン同士でも、序盤が違えば違う対局になるように使う．
+pub fn genOpenings(size: i32) -> Vec<Vec<Pos>> {
+    let mut board = Board::withSize(size);
+    board.init();
+    let mut openings = vec!();
+    for first in board.genNextBoards(&Piece::Black) {
+        for second in first.board.searchPos(&Piece::White) {
+            openings.push(vec!(first.pos, second.pos));
+        }
+    }
+    return openings;
+}
+
+// 一辺sizeマスの盤の初期配置からopeningを打った局面で1局打つ
+//
+// @return 序盤が打てない場合や、プレイヤーが打てない手を返した場合はErr
+pub fn playGame(black: &mut dyn Player, white: &mut dyn Player, size: i32, opening: &[Pos]) -> Result<GameOutcome, String> {
+    if !isValidSize(size) {
+        return Err(format!("unsupported board size {}", size));
+    }
+    let mut board = Board::withSize(size);
+    board.init();
+    let mut record = GameRecord::new(&board, &Piece::Black);
+    let mut turn = Piece::Black;
+
+    for pos in opening {
+        match board.put(&turn, pos) {
+            Some(ret) => board = ret.board,
+            None => {
+                return Err(format!("opening move {} is illegal", Pos::toDesc(pos.x, pos.y)));
+            }
+        }
+        record.push(&turn, Some(*pos));
+        turn = Piece::getOpponent(&turn);
+    }
+
+    black.reset();
+    white.reset();
+    loop {
+        if board.searchPos(&turn).len() == 0 {
+            if board.searchPos(&Piece::getOpponent(&turn)).len() == 0 {
+                // 双方置けない
+                break;
+            }
+            record.push(&turn, None);
+            turn = Piece::getOpponent(&turn);
+            continue;
+        }
+
+        let player: &mut dyn Player = if turn == Piece::Black { &mut *black } else { &mut *white };
+        match player.getMove(&board, &turn) {
+            Some(Move::Put(pos)) => {
+                match board.put(&turn, &pos) {
+                    Some(ret) => board = ret.board,
+                    None => {
+                        return Err(format!("{} played illegal move {}", player.getName(), Pos::toDesc(pos.x, pos.y)));
+                    }
+                }
+                record.push(&turn, Some(pos));
+            },
+            Some(Move::Pass) => {
+                return Err(format!("{} passed while it had a move", player.getName()));
+            },
+            None => {
+                return Err(format!("{} did not return a move", player.getName()));
+            }
+        }
+        turn = Piece::getOpponent(&turn);
+    }
+
+    return Ok(GameOutcome { record, count: board.getCount() });
+}
+
+// 勝ち、引き分け、負けの集計（一方のプレイヤーから見たもの）
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MatchResult {
+    pub wins: u32,
+    pub draws: u32,
+    pub losses: u32,
+    pub totalMargin: i32    // 石差の合計
+}
+
+impl MatchResult {
+    pub fn new() -> Self {
+        return MatchResult::default();
+    }
+
+    // 1局の石差（自分-相手）を加える
+    pub fn add(&mut self, margin: i32) {
+        if margin > 0 {
+            self.wins += 1;
+        } else if margin < 0 {
+            self.losses += 1;
+        } else {
+            self.draws += 1;
+        }
+        self.totalMargin += margin;
+    }
+
+    pub fn getGames(&self) -> u32 {
+        return self.wins + self.draws + self.losses;
+    }
+
+    // 得点率（勝ちを1、引き分けを0.5とする）
+    pub fn getScore(&self) -> f64 {
+        let n = self.getGames();
+        if n == 0 {
+            return 0.5;
+        }
+        return (self.wins as f64 + self.draws as f64 * 0.5) / n as f64;
+    }
+
+    pub fn getAverageMargin(&self) -> f64 {
+        let n = self.getGames();
+        if n == 0 {
+            return 0.0;
+        }
+        return self.totalMargin as f64 / n as f64;
+    }
+
+    // 相手とのレーティング差と、その95%信頼区間の幅（±）を返す
+    pub fn getElo(&self) -> (f64, f64) {
+        let n = self.getGames() as f64;
+        if n == 0.0 {
+            return (0.0, 0.0);
+        }
+        let p = self.getScore();
+        let variance = (self.wins as f64 * (1.0 - p).powi(2)
+            + self.draws as f64 * (0.5 - p).powi(2)
+            + self.losses as f64 * p.powi(2)) / n;
+        let sigma = (variance / n).sqrt();
+        let lower = scoreToElo(p - 1.96 * sigma);
+        let upper = scoreToElo(p + 1.96 * sigma);
+        return (scoreToElo(p), (upper - lower) / 2.0);
+    }
+}
+
+// 得点率をレーティング差にする（全勝、全敗でも有限の値になるように丸める）
+pub fn scoreToElo(score: f64) -> f64 {
+    let p = score.max(0.001).min(0.999);
+    return -400.0 * (1.0 / p - 1.0).log10();
+}
+
+// 2つのプレイヤーをnGames局対戦させる
+//
+// 一辺sizeマスの盤で打つ．序盤はOPENINGSから順に選び（getOpening）、
+// 同じ序盤で先後を入れ替えて2局ずつ打つ．
+// 1局終わるごとにonGame(局番号, 結果, aが黒ならtrue)を呼ぶ．
+//
+// @return aから見た集計
+pub fn runMatch(
+    a: &mut dyn Player,
+    b: &mut dyn Player,
+    size: i32,
+    nGames: usize,
+    onGame: &mut dyn FnMut(usize, &GameOutcome, bool)
+) -> Result<MatchResult, String> {
+    let mut result = MatchResult::new();
+
+    for i in 0..nGames {
+        let opening = getOpening(size, i / 2)?;
+        let aIsBlack = i % 2 == 0;
+        let outcome = if aIsBlack {
+            playGame(a, b, size, &opening)?
+        } else {
+            playGame(b, a, size, &opening)?
+        };
+
+        let margin = outcome.getMargin();
+        result.add(if aIsBlack { margin } else { -margin });
+        onGame(i + 1, &outcome, aIsBlack);
+    }
+
+    return Ok(result);
+}
+
+// 総当たり戦の1局（playersのインデックスで表す）
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Pairing {
+    pub black: usize,
+    pub white: usize,
+    pub opening: usize  // OPENINGSのインデックス
+}
+
+// nPlayers人の総当たり戦の組み合わせを返す
+//
+// 各組についてgamesPerPair局打つ．序盤はOPENINGSから順に選び、
+// 同じ序盤で先後を入れ替えて2局ずつ打つ．
+pub fn roundRobin(nPlayers: usize, gamesPerPair: usize) -> Vec<Pairing> {
+    let mut pairings = vec!();
+    for k in 0..gamesPerPair {
+        for i in 0..nPlayers {
+            for j in i + 1..nPlayers {
+                let opening = (k / 2) % OPENINGS.len();
+                if k % 2 == 0 {
+                    pairings.push(Pairing { black: i, white: j, opening });
+                } else {
+                    pairings.push(Pairing { black: j, white: i, opening });
+                }
+            }
+        }
+    }
+    return pairings;
+}
+
+// 総当たり戦の集計
+#[derive(Clone, Debug, PartialEq)]
+pub struct TournamentResult {
+    pub names: Vec<String>,
+    pub crossTable: Vec<Vec<MatchResult>>   // [i][j]はiから見たjとの対戦成績
+}
+
+impl TournamentResult {
+    pub fn new(names: &[String]) -> Self {
+        let n = names.len();
+        return TournamentResult {
+            names: names.to_vec(),
+            crossTable: vec![vec![MatchResult::new(); n]; n]
+        };
+    }
+
+    pub fn add(&mut self, pairing: &Pairing, outcome: &GameOutcome) {
+        let margin = outcome.getMargin();
+        self.crossTable[pairing.black][pairing.white].add(margin);
+        self.crossTable[pairing.white][pairing.black].add(-margin);
+    }
+
+    // iの全対局の成績
+    pub fn getTotal(&self, i: usize) -> MatchResult {
+        let mut total = MatchResult::new();
+        for r in &self.crossTable[i] {
+            total.wins += r.wins;
+            total.draws += r.draws;
+            total.losses += r.losses;
+            total.totalMargin += r.totalMargin;
+        }
+        return total;
+    }
+
+    // 各プレイヤーのレーティング（平均が0）を返す
+    //
+    // 引き分けを0.5勝として、Bradley-Terryモデルの最尤推定をMMアルゴリズムで求める．
+    // BayesEloと同様に、各プレイヤーにレーティング0の仮想的な相手との2つの引き分けを
+    // 事前分布として加えるので、全勝や全敗でも有限の値になる．
+    pub fn getRatings(&self) -> Vec<f64> {
+        let n = self.names.len();
+        let mut gammas = vec![1.0; n];
+
+        for _ in 0..1000 {
+            let mut maxChange: f64 = 0.0;
+            for i in 0..n {
+                let total = self.getTotal(i);
+                let score = total.wins as f64 + total.draws as f64 * 0.5 + 1.0;
+                let mut denom = 2.0 / (gammas[i] + 1.0);
+                for j in 0..n {
+                    let games = self.crossTable[i][j].getGames() as f64;
+                    if j != i && games > 0.0 {
+                        denom += games / (gammas[i] + gammas[j]);
+                    }
+                }
+                let gamma = score / denom;
+                maxChange = maxChange.max((gamma / gammas[i]).ln().abs());
+                gammas[i] = gamma;
+            }
+            if maxChange < 1e-9 {
+                break;
+            }
+        }
+
+        let ratings = gammas.iter().map(|g| 400.0 * g.log10()).collect::<Vec<f64>>();
+        let mean = ratings.iter().sum::<f64>() / n.max(1) as f64;
+        return ratings.iter().map(|r| r - mean).collect();
+    }
+
+    // レーティング順の成績表と対戦表を文字列にする
+    pub fn format(&self) -> String {
+        let n = self.names.len();
+        let ratings = self.getRatings();
+        let mut order = (0..n).collect::<Vec<usize>>();
+        order.sort_by(|&a, &b| ratings[b].partial_cmp(&ratings[a]).unwrap());
+
+        let nameWidth = self.names.iter().map(|s| s.len()).max().unwrap_or(0).max(6);
+        let mut text = format!(
+            "{:>3}  {:<w$}  {:>5}  {:>4} {:>4} {:>4}  {:>6}  {:>7}  {:>5}\n",
+            "#", "player", "games", "W", "D", "L", "score", "margin", "Elo", w = nameWidth
+        );
+        for (rank, &i) in order.iter().enumerate() {
+            let total = self.getTotal(i);
+            text.push_str(&format!(
+                "{:>3}  {:<w$}  {:>5}  {:>4} {:>4} {:>4}  {:>5.1}%  {:>+7.2}  {:>+5.0}\n",
+                rank + 1, self.names[i], total.getGames(), total.wins, total.draws, total.losses,
+                total.getScore() * 100.0, total.getAverageMargin(), ratings[i], w = nameWidth
+            ));
+        }
+
+        // 対戦表（行のプレイヤーの得点/局数）
+        text.push('\n');
+        text.push_str(&format!("{:>3}  {:<w$}", "", "", w = nameWidth));
+        for rank in 0..n {
+            text.push_str(&format!("  {:>7}", rank + 1));
+        }
+        text.push('\n');
+        for (rank, &i) in order.iter().enumerate() {
+            text.push_str(&format!("{:>3}  {:<w$}", rank + 1, self.names[i], w = nameWidth));
+            for &j in &order {
+                let r = &self.crossTable[i][j];
+                if i == j || r.getGames() == 0 {
+                    text.push_str(&format!("  {:>7}", "-"));
+                } else {
+                    let points = r.wins as f64 + r.draws as f64 * 0.5;
+                    text.push_str(&format!("  {:>7}", format!("{}/{}", points, r.getGames())));
+                }
+            }
+            text.push('\n');
+        }
+        return text;
+    }
+}
+
+// 1局の記録を棋譜ファイルの形式にする
+//
+// 先頭にPGNのような[Tag "value"]の行を付ける．後ろはGameRecord::formatと同じなので、
+// GameRecord::parseでそのまま読める．
+pub fn formatGameLog(event: &str, round: usize, blackName: &str, whiteName: &str, outcome: &GameOutcome) -> String {
+    let result = if outcome.count.nBlackPieces > outcome.count.nWhitePieces {
+        "1-0"
+    } else if outcome.count.nBlackPieces < outcome.count.nWhitePieces {
+        "0-1"
+    } else {
+        "1/2-1/2"
+    };
+
+    let mut text = String::new();
+    text.push_str(&format!("[Event \"{}\"]\n", event));
+    text.push_str(&format!("[Round \"{}\"]\n", round));
+    text.push_str(&format!("[Black \"{}\"]\n", blackName));
+    text.push_str(&format!("[White \"{}\"]\n", whiteName));
+    text.push_str(&format!("[Score \"{}-{}\"]\n", outcome.count.nBlackPieces, outcome.count.nWhitePieces));
+    text.push_str(&format!("[Result \"{}\"]\n", result));
+    text.push_str(&outcome.record.format());
+    return text;
+}
+
+// specsのプレイヤーで一辺sizeマスの盤の総当たり戦を行う
+//
+// nThreads個のスレッドで並行して対局する．プレイヤーは対局ごとにspecから作り直す．
+// 1局終わるごとに、呼び出したスレッドでonGame(局番号, 組み合わせ, 結果)を呼ぶ
+// （局番号はroundRobinの順で1から、呼ばれる順は終わった順）．
+pub fn runTournament(
+    specs: &[String],
+    size: i32,
+    gamesPerPair: usize,
+    nThreads: usize,
+    onGame: &mut dyn FnMut(usize, &Pairing, &GameOutcome)
+) -> Result<TournamentResult, String> {
+    // 先にすべてのspecを確かめておく
+    for spec in specs {
+        createPlayer(spec)?;
+    }
+
+    let pairings = roundRobin(specs.len(), gamesPerPair);
+    let queue = Arc::new(Mutex::new(pairings.iter().cloned().enumerate().collect::<VecDeque<(usize, Pairing)>>()));
+    let (tx, rx) = channel();
+
+    for _ in 0..nThreads.max(1) {
+        let queue = Arc::clone(&queue);
+        let tx = tx.clone();
+        let specs = specs.to_vec();
+        thread::spawn(move || {
+            loop {
+                let optJob = queue.lock().unwrap().pop_front();
+                let (i, pairing) = match optJob {
+                    Some(job) => job,
+                    None => {
+                        break;
+                    }
+                };
+                let result = createPlayer(&specs[pairing.black]).and_then(|mut black| {
+                    let mut white = createPlayer(&specs[pairing.white])?;
+                    let opening = getOpening(size, pairing.opening)?;
+                    playGame(&mut *black, &mut *white, size, &opening)
+                });
+                if tx.send((i, pairing, result)).is_err() {
+                    // 呼び出し側がやめた
+                    break;
+                }
+            }
+        });
+    }
+    drop(tx);
+
+    let mut tournament = TournamentResult::new(specs);
+    for (i, pairing, result) in rx {
+        let outcome = result?;
+        tournament.add(&pairing, &outcome);
+        onGame(i + 1, &pairing, &outcome);
+    }
+    return Ok(tournament);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::record::RecordMove;
+
+    fn createTournament(results: &[(usize, usize, i32)]) -> TournamentResult {
+        let names = ["a", "b", "c"].iter().map(|s| s.to_string()).collect::<Vec<String>>();
+        let mut tournament = TournamentResult::new(&names);
+        for &(i, j, margin) in results {
+            tournament.crossTable[i][j].add(margin);
+            tournament.crossTable[j][i].add(-margin);
+        }
+        return tournament;
+    }
+
+    fn assertNear(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 0.01, "{} != {}", actual, expected);
+    }
+
+    #[test]
+    fn matchElo() {
+        let mut result = MatchResult::new();
+        for &margin in &[10, 2, -4, 6] {
+            result.add(margin);
+        }
+        assert_eq!((result.wins, result.draws, result.losses), (3, 0, 1));
+        assertNear(result.getScore(), 0.75);
+        assertNear(result.getAverageMargin(), 3.5);
+        let (elo, margin) = result.getElo();
+        assertNear(elo, 190.85);
+        assertNear(margin, 663.14);
+
+        assertNear(scoreToElo(0.5), 0.0);
+        assertNear(scoreToElo(1.0), -scoreToElo(0.0));
+    }
+
+    #[test]
+    fn ratings() {
+        // aがbに3勝1敗（cは対局なし）
+        let ratings = createTournament(&[(0, 1, 2), (0, 1, 2), (0, 1, 2), (1, 0, 2)]).getRatings();
+        assertNear(ratings[0] - ratings[1], 145.79);
+        assertNear(ratings.iter().sum::<f64>(), 0.0);
+
+        // aが全勝、bとcは1勝1敗
+        let ratings = createTournament(&[(0, 1, 8), (0, 1, 8), (0, 2, 8), (0, 2, 8), (1, 2, 4), (2, 1, 4)]).getRatings();
+        assertNear(ratings[0], 211.25);
+        assertNear(ratings[1], -105.63);
+        assertNear(ratings[2], -105.63);
+    }
+
+    #[test]
+    fn roundRobinPairings() {
+        let pairings = roundRobin(3, 4);
+        assert_eq!(pairings.len(), 12);
+        assert_eq!(pairings[0], Pairing { black: 0, white: 1, opening: 0 });
+        assert_eq!(pairings[3], Pairing { black: 1, white: 0, opening: 0 });
+        assert_eq!(pairings[6], Pairing { black: 0, white: 1, opening: 1 });
+        for i in 0..3 {
+            assert_eq!(pairings.iter().filter(|p| p.black == i || p.white == i).count(), 8);
+        }
+    }
+
+    #[test]
+    fn createPlayerSpecs() {
+        for spec in &["random:seed=1", "greedy", "level:easy", "level:normal,seed=3", "engine:5", "engine:depth=3,time=100,random=tie,seed=1", "mcts:100"] {
+            assert!(createPlayer(spec).is_ok(), "{}", spec);
+        }
+        for spec in &["engine:0", "engine:depth=x", "engine:foo=1", "level:foo", "level:easy,seed=x", "level:easy,foo=1", "human"] {
+            assert!(createPlayer(spec).is_err(), "{}", spec);
+        }
+    }
+
+    // seedを指定したプレイヤー同士で1局打ち、手順を返す
+    fn playSeeded(seed: u64) -> Vec<RecordMove> {
+        let mut black = createPlayer(&format!("level:beginner,seed={}", seed)).unwrap();
+        let mut white = createPlayer(&format!("engine:3,random=tie,seed={}", seed)).unwrap();
+        return playGame(&mut *black, &mut *white, 8, &[]).unwrap().record.moves;
+    }
+
+    #[test]
+    fn seededGames() {
+        assert_eq!(playSeeded(1), playSeeded(1));
+        assert_ne!(playSeeded(1), playSeeded(2));
+    }
+
+    #[test]
+    fn openingsForEverySize() {
+        assert_eq!(getOpening(8, 1), parseMoves(OPENINGS[1]));
+        for size in (4..=16).step_by(2) {
+            let openings = (0..8).map(|i| getOpening(size, i).unwrap()).collect::<Vec<Vec<Pos>>>();
+            for (i, opening) in openings.iter().enumerate() {
+                assert!(opening.len() >= 2, "size {}", size);
+                assert!(!openings[..i].contains(opening), "size {}: opening {} is repeated", size, i);
+                let mut black = GreedyPlayer;
+                let mut white = GreedyPlayer;
+                assert!(playGame(&mut black, &mut white, size, opening).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn deterministicEnginesPlayDifferentGames() {
+        let mut a = createPlayer("engine:2").unwrap();
+        let mut b = createPlayer("engine:3").unwrap();
+        let mut games = vec!();
+        runMatch(&mut *a, &mut *b, 6, 6, &mut |_, outcome, _| games.push(outcome.record.moves.clone())).unwrap();
+        assert_ne!(games[0], games[2]);
+        assert_ne!(games[2], games[4]);
+    }
+}
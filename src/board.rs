@@ -1,7 +1,12 @@
 #![allow(non_snake_case)]
 
+use std::fmt;
 use std::io::{BufRead, BufReader};
 use std::fs::File;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+
+use crate::search;
 
 // 駒
 #[derive(Copy, Clone, Debug, PartialEq)]
@@ -31,23 +36,33 @@ impl Piece {
     }
 }
 
+// 盤の一辺のマス数（偶数のみ）
+pub const MIN_SIZE: i32 = 4;
+pub const MAX_SIZE: i32 = 16;
+pub const DEFAULT_SIZE: i32 = 8;
+
+// 盤の一辺のマス数として使えればtrue
+pub fn isValidSize(size: i32) -> bool {
+    return (size >= MIN_SIZE) && (size <= MAX_SIZE) && (size % 2 == 0);
+}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Pos {
-    pub x: i32,  // 1..8
-    pub y: i32   // 1..8
+    pub x: i32,  // 1..size
+    pub y: i32   // 1..size
 }
 
 impl Pos {
-    // Pos構造体からBoardのインデックスを返す
+    // Pos構造体から一辺sizeマスのBoardのインデックスを返す
     //
     // (ex)
     // pub fn toIdx(&self) -> usize {
     //     return ((self.y-1) * 8 + (self.x-1)).into();
     // }
 
-    pub fn idx(x: i32, y: i32) -> Option<usize> {
-        if (x >= 1) && (x <= 8) && (y >= 1) && (y <= 8) {
-            return Some(((y-1) * 8 + (x-1)) as usize);
+    pub fn idx(x: i32, y: i32, size: i32) -> Option<usize> {
+        if (x >= 1) && (x <= size) && (y >= 1) && (y <= size) {
+            return Some(((y-1) * size + (x-1)) as usize);
         } else {
             return None;
         }
@@ -67,19 +82,45 @@ impl Pos {
         };
     }
 
+    // 列の名前（1..16 → "A".."P"）
+    pub fn getColumnName(x: i32) -> String {
+        if (x >= 1) && (x <= MAX_SIZE) {
+            return ((b'A' + (x - 1) as u8) as char).to_string();
+        }
+        return " ".to_string();
+    }
+
     pub fn toDesc(x: i32, y: i32) -> String {
-        let xstr = match x {
-            1 => "A",
-            2 => "B",
-            3 => "C",
-            4 => "D",
-            5 => "E",
-            6 => "F",
-            7 => "G",
-            8 => "H",
-            _ => " "
+        return format!("{}{}", Pos::getColumnName(x), y);
+    }
+
+    // "F5"、"P16"のような文字列からPosを返す（toDescの逆）
+    //
+    // 大文字、小文字どちらでもよい．最も大きい盤の範囲外ならNone
+    // （小さい盤の範囲内かどうかは盤の側で調べる）
+    pub fn fromDesc(desc: &str) -> Option<Pos> {
+        let desc = desc.trim().to_uppercase();
+        let mut chars = desc.chars();
+        let x = match chars.next() {
+            Some(c) if c.is_ascii_uppercase() => (c as u8 - b'A') as i32 + 1,
+            _ => {
+                return None;
+            }
         };
-        return format!("{}{}", xstr, y);
+        let rest = chars.as_str();
+        if (rest.len() == 0) || !rest.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+        let y = match rest.parse::<i32>() {
+            Ok(y) => y,
+            Err(_) => {
+                return None;
+            }
+        };
+        if (x > MAX_SIZE) || (y < 1) || (y > MAX_SIZE) {
+            return None;
+        }
+        return Some(Pos { x, y });
     }
 }
 
@@ -122,50 +163,77 @@ pub struct Count {
     pub nBlackPieces: i32
 }
 
+// 盤の表示と同じ記号で個数を表す
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        return write!(f, "●={}, ○={}", self.nWhitePieces, self.nBlackPieces);
+    }
+}
+
+// 探索を途中で打ち切る条件
+//
+// abortがtrueになるか、optDeadlineを過ぎたら打ち切る．
+#[derive(Copy, Clone, Debug)]
+pub struct SearchStop<'a> {
+    pub abort: &'a AtomicBool,
+    pub optDeadline: Option<Instant>
+}
+
+impl<'a> SearchStop<'a> {
+    pub fn new(abort: &'a AtomicBool) -> Self {
+        return SearchStop { abort, optDeadline: None };
+    }
+
+    pub fn withDeadline(abort: &'a AtomicBool, optDeadline: Option<Instant>) -> Self {
+        return SearchStop { abort, optDeadline };
+    }
+
+    pub fn isStopped(&self) -> bool {
+        return self.abort.load(Ordering::Relaxed) || self.optDeadline.map_or(false, |deadline| Instant::now() >= deadline);
+    }
+}
+
 // オセロ盤
 #[derive(Clone, Debug, PartialEq)]
 pub struct Board {
+    size: i32,      // 一辺のマス数
     pieces: Vec<Piece>,
     coefs: Vec<i32> // スコア計算用の係数（盤上の場所ごとに決まる）
 }
 
 impl Board {
-    // 空のボードを作る
+    // 空のボードを作る（8x8）
     pub fn new() -> Self {
+        return Board::withSize(DEFAULT_SIZE);
+    }
+
+    // 一辺sizeマスの空のボードを作る
+    //
+    // sizeはisValidSizeで調べておくこと
+    pub fn withSize(size: i32) -> Self {
+        assert!(isValidSize(size), "invalid board size {}", size);
+
         let mut pieces = Vec::<Piece>::new();
-        for _y in 1..=8 {
-            for _x in 1..=8 {
+        for _y in 1..=size {
+            for _x in 1..=size {
                 pieces.push(Piece::Space);
             }
         }
 
+        // 端からの距離（0始まり）
+        let edge = |v: i32| (v - 1).min(size - v);
         let mut coefs = Vec::<i32>::new();
-        for _y in 1..=8 {
-            for _x in 1..=8 {
+        for _y in 1..=size {
+            for _x in 1..=size {
                 let mut c = 1;
-                if 
-                    ((_x == 1) && (_y == 1)) ||
-                    ((_x == 8) && (_y == 1)) ||
-                    ((_x == 1) && (_y == 8)) ||
-                    ((_x == 8) && (_y == 8)) {
-                        // 4隅はスコアを上げる
-                        c = 12;
+                let (ex, ey) = (edge(_x), edge(_y));
+                if (ex == 0) && (ey == 0) {
+                    // 4隅はスコアを上げる
+                    c = 12;
                 }
-                else if
-                    ((_x == 2) && (_y == 1)) ||
-                    ((_x == 1) && (_y == 2)) ||
-                    ((_x == 2) && (_y == 2)) ||
-                    ((_x == 7) && (_y == 1)) ||
-                    ((_x == 8) && (_y == 2)) ||
-                    ((_x == 7) && (_y == 2)) ||
-                    ((_x == 1) && (_y == 7)) ||
-                    ((_x == 2) && (_y == 8)) ||
-                    ((_x == 2) && (_y == 7)) ||
-                    ((_x == 8) && (_y == 7)) ||
-                    ((_x == 7) && (_y == 8)) ||
-                    ((_x == 7) && (_y == 7)) {
-                        // 4隅の隣はスコアを下げる
-                        c = -4;
+                else if (ex <= 1) && (ey <= 1) {
+                    // 4隅の隣はスコアを下げる
+                    c = -4;
                 }
 
                 coefs.push(c);
@@ -173,54 +241,63 @@ impl Board {
         }
 
         return Board {
+            size: size,
             pieces: pieces,
             coefs: coefs
         };
     }
 
-    // コンソールに表示する
-    pub fn print(&self) -> () {
-        println!("   A B C D E F G H");
-        println!(" -----------------");
-        for y in 1..=8 {
-            print!("{}|", y);
-            for x in 1..=8 {
-                if let Some(idx) = Pos::idx(x, y) {
-                    let piece = &self.pieces[idx];
-                    let pstr = match piece {
-                        Piece::White => "●",
-                        Piece::Black => "○",
-                        _ => "・"
-                    };
-                    print!("{}", pstr);    
-                }
-            }
-            println!("|{}", y);
-        }
-        println!(" -----------------");
-        println!("   A B C D E F G H");
+    // 一辺のマス数
+    pub fn getSize(&self) -> i32 {
+        return self.size;
     }
 
-    // 初期状態にする
+    // 初期状態にする（中央の4マスに置く）
     pub fn init(&mut self) -> () {
-        self.setPiece(4, 4, Piece::White);
-        self.setPiece(5, 5, Piece::White);
-        self.setPiece(4, 5, Piece::Black);
-        self.setPiece(5, 4, Piece::Black);
+        let c = self.size / 2;
+        self.setPiece(c, c, Piece::White);
+        self.setPiece(c + 1, c + 1, Piece::White);
+        self.setPiece(c, c + 1, Piece::Black);
+        self.setPiece(c + 1, c, Piece::Black);
     }
 
+    // 盤の表示（Display）と同じ形式のファイルを読み込む
+    //
+    // 盤の大きさは最初の行の'|'に挟まれた駒の数で決まり、このボードと違えば作り直す．
+    // '|'がなければこのボードと同じ大きさとする．扱えない大きさならfalse
     pub fn load(&mut self, boardPath: &str) -> bool {
         if let Some(lines) = self.readTextFile(boardPath).ok() {
-            for i in 2..10 {
+            if let Some(line) = lines.get(2) {
+                let cells = line.split('|').collect::<Vec<&str>>();
+                if cells.len() >= 3 {
+                    let size = cells[1].chars().count() as i32;
+                    if !isValidSize(size) {
+                        return false;
+                    }
+                    if size != self.size {
+                        *self = Board::withSize(size);
+                    }
+                }
+            }
+            let n = self.size as usize;
+            if lines.len() < n + 2 {
+                return false;
+            }
+            for i in 2..(n + 2) {
                 let line = lines[i].chars().collect::<Vec<char>>();
-                for j in 2..10 {
-                    let ch = line[j];
+                // 駒は行番号の後の'|'から始まる
+                let start = line.iter().position(|&c| c == '|').map_or(2, |p| p + 1);
+                for j in 0..n {
+                    if start + j >= line.len() {
+                        break;
+                    }
+                    let ch = line[start + j];
                     let piece = match ch {
                         '●' => Piece::White,
                         '○' => Piece::Black,
                         _ => Piece::Space
                     };
-                    let x = (j-1) as i32;
+                    let x = (j+1) as i32;
                     let y = (i-1) as i32;
                     self.setPiece(x, y, piece);
                 }
@@ -252,21 +329,32 @@ impl Board {
     }
     
     pub fn setPiece(&mut self, x: i32, y: i32, piece: Piece) -> () {
-        if let Some(idx) = Pos::idx(x, y) {
+        if let Some(idx) = Pos::idx(x, y, self.size) {
             self.pieces[idx] = piece;
         }
     }
 
     pub fn getPiece(&self, x: i32, y: i32) -> Option<&Piece> {
-        if let Some(idx) = Pos::idx(x, y) {
+        if let Some(idx) = Pos::idx(x, y, self.size) {
             return Some(&self.pieces[idx]);
         } else {
             return None;
         }
     }
 
+    // スコア計算用の係数を置き換える（左上から行ごとにsize*size個）
+    //
+    // @return 個数が合わない場合はfalse（係数は変わらない）
+    pub fn setCoefs(&mut self, coefs: &[i32]) -> bool {
+        if coefs.len() != self.coefs.len() {
+            return false;
+        }
+        self.coefs = coefs.to_vec();
+        return true;
+    }
+
     pub fn getCoef(&self, x: i32, y: i32) -> i32 {
-        if let Some(idx) = Pos::idx(x, y) {
+        if let Some(idx) = Pos::idx(x, y, self.size) {
             return self.coefs[idx];
         } else {
             return 0;
@@ -276,8 +364,8 @@ impl Board {
     // pieceが次に置ける場所を全て探す
     pub fn searchPos(&self, piece: &Piece) -> Vec<SearchResult1> {
         let mut result = Vec::new();
-        for y in 1..=8 {
-            for x in 1..=8 {
+        for y in 1..=self.size {
+            for x in 1..=self.size {
                 if let Some(p) = self.getPiece(x, y) {
                     if *p == Piece::Space {
                         if let Some(res) = self.searchPosSub(piece, &Pos{x, y}) {
@@ -459,20 +547,38 @@ impl Board {
 
     // pieceの手番でdepth手先まで読む
     pub fn genSearchTree(&self, piece: &Piece, depth: i32) -> Vec<SearchResult3> {
+        return self.genSearchTreeWithAbort(piece, depth, &AtomicBool::new(false));
+    }
+
+    // genSearchTreeと同じだが、abortがtrueになったら途中で打ち切る
+    // （打ち切った場合の結果は不完全）
+    pub fn genSearchTreeWithAbort(&self, piece: &Piece, depth: i32, abort: &AtomicBool) -> Vec<SearchResult3> {
         let root = SearchResult3 {
             path: vec!(),
             board: self.clone(),
             ntake: 0,
             score: 0
         };
-        return self.genSearchTreeSub(piece, piece, depth, &root);
+        return self.genSearchTreeSubWithStats(piece, piece, depth, &root, &SearchStop::new(abort), &mut 0);
     }
 
     pub fn genSearchTreeSub(&self, origPiece: &Piece, piece: &Piece, depth: i32, tree: &SearchResult3) -> Vec<SearchResult3> {
+        return self.genSearchTreeSubWithStats(origPiece, piece, depth, tree, &SearchStop::new(&AtomicBool::new(false)), &mut 0);
+    }
+
+    // genSearchTreeSubと同じだが、stopの条件を満たしたら途中で打ち切り、
+    // 生成した局面の数をnodesに足していく
+    pub fn genSearchTreeSubWithStats(&self, origPiece: &Piece, piece: &Piece, depth: i32, tree: &SearchResult3, stop: &SearchStop, nodes: &mut u64) -> Vec<SearchResult3> {
         let mut results = vec!();
 
+        if stop.isStopped() {
+            // 中断された
+            return results;
+        }
+
         if depth > 0 {
             let nextBoards: Vec<SearchResult2> = self.genNextBoards(piece);
+            *nodes += nextBoards.len() as u64;
             if nextBoards.len() == 0 {
                 // println!("no next board found! for {}", piece.to_str());
                 // self.print();
@@ -527,11 +633,13 @@ impl Board {
                         ntake: newNtake,
                         score: newScore
                     };
-                    let childResults = nextBoard.board.genSearchTreeSub(
+                    let childResults = nextBoard.board.genSearchTreeSubWithStats(
                         origPiece,
                         &Piece::getOpponent(piece),
                         newDepth,
-                        &newTree
+                        &newTree,
+                        stop,
+                        nodes
                     );
                     for childResult in &childResults {
                         results.push(childResult.clone());
@@ -552,10 +660,15 @@ impl Board {
 
     // 最善の手を探す
     pub fn getBestMove(&self, piece: &Piece, depth: i32) -> Option<SearchResult3> {
+        return self.getBestMoveWithAbort(piece, depth, &AtomicBool::new(false));
+    }
+
+    // getBestMoveと同じだが、abortがtrueになったら探索を打ち切る
+    // （打ち切った場合の結果は最善とは限らない）
+    pub fn getBestMoveWithAbort(&self, piece: &Piece, depth: i32, abort: &AtomicBool) -> Option<SearchResult3> {
         let mut bestMove = None;
 
-        let allMoves: Vec<SearchResult3> = self.genSearchTree(piece, depth);
-        println!("{} moves", allMoves.len());
+        let allMoves: Vec<SearchResult3> = self.genSearchTreeWithAbort(piece, depth, abort);
         let mut bestScore = std::i32::MIN;
         for m in &allMoves {
             // let n = m.path.len();
@@ -576,14 +689,35 @@ impl Board {
         return bestMove;
     }
 
+    // pieceの置ける場所ごとに、depth手先まで読んだ場合のスコアを返す
+    //
+    // スコアの意味はgetBestMoveと同じ（値が大きいほどpieceに有利）
+    pub fn getMoveValues(&self, piece: &Piece, depth: i32) -> Vec<(Pos, i32)> {
+        return self.getMoveValuesWithAbort(piece, depth, &AtomicBool::new(false));
+    }
+
+    // getMoveValuesと同じだが、abortがtrueになったら探索を打ち切る
+    // （打ち切った場合の結果は不完全）
+    //
+    // 読み筋や局面の数も必要ならsearch::searchを使う
+    pub fn getMoveValuesWithAbort(&self, piece: &Piece, depth: i32, abort: &AtomicBool) -> Vec<(Pos, i32)> {
+        return search::search(self, piece, depth, &SearchStop::new(abort)).moveValues;
+    }
+
+    // 空きマスの数
+    pub fn getEmptyCount(&self) -> i32 {
+        let count = self.getCount();
+        return self.size * self.size - count.nWhitePieces - count.nBlackPieces;
+    }
+
     // 白、黒が盤上に何個あるか数える
     pub fn getCount(&self) -> Count {
         let mut nWhitePieces = 0;
         let mut nBlackPieces = 0;
 
-        for y in 1..=8 {
-            for x in 1..=8 {
-                if let Some(idx) = Pos::idx(x, y) {
+        for y in 1..=self.size {
+            for x in 1..=self.size {
+                if let Some(idx) = Pos::idx(x, y, self.size) {
                     let piece = &self.pieces[idx];
                     match piece {
                         Piece::White => {
@@ -602,9 +736,102 @@ impl Board {
 
         Count { nWhitePieces, nBlackPieces }
     }
+}
+
+// コンソール表示用の文字列（最後の改行は含まない）
+//
+// (ex)
+// println!("{}", board);
+impl fmt::Display for Board {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // 行番号が2桁になる盤では右に揃える
+        let w = if self.size > 9 { 2 } else { 1 };
+        let columns = (1..=self.size).map(|x| Pos::getColumnName(x)).collect::<Vec<String>>().join(" ");
+        let rule = "-".repeat((self.size * 2 + 1) as usize);
+        writeln!(f, "{:w$}  {}", "", columns, w = w)?;
+        writeln!(f, "{:w$}{}", "", rule, w = w)?;
+        for y in 1..=self.size {
+            write!(f, "{:>w$}|", y, w = w)?;
+            for x in 1..=self.size {
+                if let Some(idx) = Pos::idx(x, y, self.size) {
+                    let piece = &self.pieces[idx];
+                    let pstr = match piece {
+                        Piece::White => "●",
+                        Piece::Black => "○",
+                        _ => "・"
+                    };
+                    write!(f, "{}", pstr)?;
+                }
+            }
+            writeln!(f, "|{}", y)?;
+        }
+        writeln!(f, "{:w$}{}", "", rule, w = w)?;
+        return write!(f, "{:w$}  {}", "", columns, w = w);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn sizes() -> impl Iterator<Item = i32> {
+        return (MIN_SIZE..=MAX_SIZE).filter(|&size| isValidSize(size));
+    }
+
+    #[test]
+    fn validSizes() {
+        assert_eq!(sizes().collect::<Vec<i32>>(), vec!(4, 6, 8, 10, 12, 14, 16));
+        assert!(!isValidSize(7));
+        assert!(!isValidSize(18));
+    }
+
+    #[test]
+    fn descRoundTrip() {
+        for size in sizes() {
+            for y in 1..=size {
+                for x in 1..=size {
+                    assert_eq!(Pos::fromDesc(&Pos::toDesc(x, y)), Some(Pos { x, y }));
+                }
+            }
+        }
+        assert_eq!(Pos::fromDesc("p16"), Some(Pos { x: 16, y: 16 }));
+        assert_eq!(Pos::fromDesc("Q1"), None);
+        assert_eq!(Pos::fromDesc("A17"), None);
+        assert_eq!(Pos::fromDesc("A0"), None);
+    }
+
+    #[test]
+    fn initialPosition() {
+        for size in sizes() {
+            let mut board = Board::withSize(size);
+            board.init();
+            let c = size / 2;
+            assert_eq!(board.getPiece(c, c), Some(&Piece::White));
+            assert_eq!(board.getPiece(c + 1, c + 1), Some(&Piece::White));
+            assert_eq!(board.getPiece(c, c + 1), Some(&Piece::Black));
+            assert_eq!(board.getPiece(c + 1, c), Some(&Piece::Black));
+            assert_eq!(board.getEmptyCount(), size * size - 4);
+            assert_eq!(board.searchPos(&Piece::Black).len(), 4);
+        }
+    }
 
-    pub fn printScore(&self) {
-        let score = self.getCount();
-        println!("●={}, ○={}", score.nWhitePieces, score.nBlackPieces);
+    #[test]
+    fn displayAndLoad() {
+        let path = std::env::temp_dir().join(format!("othello-board-test-{}.txt", std::process::id()));
+        let path = path.to_str().unwrap();
+        for size in sizes() {
+            let mut board = Board::withSize(size);
+            board.init();
+            board.setPiece(1, size, Piece::Black);
+            board.setPiece(size, 1, Piece::White);
+            fs::write(path, board.to_string()).unwrap();
+
+            // 大きさはファイルから決まる
+            let mut loaded = Board::new();
+            assert!(loaded.load(path), "size {}", size);
+            assert_eq!(loaded, board);
+        }
+        fs::remove_file(path).unwrap();
     }
 }
@@ -0,0 +1,165 @@
+#![allow(non_snake_case)]
+
+use std::time::{Duration, Instant};
+
+use crate::board::Piece;
+
+// 対局時計
+//
+// 持ち時間を使い切った側は負け．手を打つたびにincrementだけ持ち時間が増える
+// （フィッシャー方式）．incrementが0なら切れ負け．
+#[derive(Clone, Debug, PartialEq)]
+pub struct Clock {
+    pub initial: Duration,      // 最初の持ち時間
+    pub increment: Duration,    // 1手ごとに加える時間
+    remaining: [Duration; 2],   // 残り時間（黒、白の順）．動いている側は動き始めたときの値
+    running: Option<(Piece, Instant)>   // 動いている側と動き始めた時刻
+}
+
+fn getSlot(piece: &Piece) -> usize {
+    return if *piece == Piece::White { 1 } else { 0 };
+}
+
+impl Clock {
+    pub fn new(initial: Duration, increment: Duration) -> Self {
+        return Clock {
+            initial,
+            increment,
+            remaining: [initial, initial],
+            running: None
+        };
+    }
+
+    // 持ち時間を最初に戻して止める
+    pub fn reset(&mut self) {
+        self.remaining = [self.initial, self.initial];
+        self.running = None;
+    }
+
+    // pieceの時計を動かす（動いていた側の時計は止める）
+    pub fn start(&mut self, piece: &Piece) {
+        self.pause();
+        self.running = Some((*piece, Instant::now()));
+    }
+
+    // 動いている時計を止める．増加時間は加えない
+    pub fn pause(&mut self) {
+        if let Some((piece, start)) = self.running.take() {
+            let slot = getSlot(&piece);
+            self.remaining[slot] = self.remaining[slot].checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0));
+        }
+    }
+
+    // 手を打ったので時計を止め、増加時間を加える
+    //
+    // @return 打つ前に時間切れになっていたらfalse
+    pub fn punch(&mut self) -> bool {
+        let optPiece = self.getRunning();
+        self.pause();
+        if let Some(piece) = optPiece {
+            if self.isFlagged(&piece) {
+                return false;
+            }
+            self.remaining[getSlot(&piece)] += self.increment;
+        }
+        return true;
+    }
+
+    // 時計が動いている側
+    pub fn getRunning(&self) -> Option<Piece> {
+        return self.running.map(|(piece, _)| piece);
+    }
+
+    // pieceの残り時間
+    pub fn getRemaining(&self, piece: &Piece) -> Duration {
+        let remaining = self.remaining[getSlot(piece)];
+        return match self.running {
+            Some((p, start)) if p == *piece => remaining.checked_sub(start.elapsed()).unwrap_or(Duration::from_secs(0)),
+            _ => remaining
+        };
+    }
+
+    // pieceが時間切れならtrue
+    pub fn isFlagged(&self, piece: &Piece) -> bool {
+        return self.getRemaining(piece) == Duration::from_secs(0);
+    }
+}
+
+// 残り時間remainingから1手に使う時間を決める
+//
+// 自分の残りの手数を空きマスの半分と見積もって均等に割り、少し余裕を残す．
+// 増加時間があればその大部分も使う．ただし残り時間の半分は超えない．
+pub fn allocateTime(remaining: Duration, increment: Duration, nEmpty: i32) -> Duration {
+    let nMovesLeft = ((nEmpty + 1) / 2).max(1) as u32;
+    let budget = remaining / (nMovesLeft + 2) + increment * 3 / 4;
+    return budget.min(remaining / 2);
+}
+
+// 残り時間を"m:ss"（1分未満は"s.s"）の形式にする
+pub fn formatDuration(d: Duration) -> String {
+    let secs = d.as_secs();
+    if secs < 60 {
+        return format!("{:.1}", d.as_secs_f64());
+    }
+    return format!("{}:{:02}", secs / 60, secs % 60);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn punchAndIncrement() {
+        let mut clock = Clock::new(Duration::from_millis(500), Duration::from_millis(100));
+        clock.start(&Piece::Black);
+        assert_eq!(clock.getRunning(), Some(Piece::Black));
+        thread::sleep(Duration::from_millis(20));
+        assert!(clock.punch());
+        assert_eq!(clock.getRunning(), None);
+
+        let black = clock.getRemaining(&Piece::Black);
+        assert!(black > Duration::from_millis(500) && black <= Duration::from_millis(580), "{:?}", black);
+        assert_eq!(clock.getRemaining(&Piece::White), Duration::from_millis(500));
+
+        // 止めている間は減らない
+        thread::sleep(Duration::from_millis(10));
+        assert_eq!(clock.getRemaining(&Piece::Black), black);
+
+        // 別の側を動かすと前の側は止まる（増加時間は加えない）
+        clock.start(&Piece::White);
+        clock.start(&Piece::Black);
+        assert!(clock.getRemaining(&Piece::White) < Duration::from_millis(500));
+        clock.reset();
+        assert_eq!(clock.getRemaining(&Piece::Black), Duration::from_millis(500));
+        assert_eq!(clock.getRunning(), None);
+    }
+
+    #[test]
+    fn flag() {
+        let mut clock = Clock::new(Duration::from_millis(10), Duration::from_secs(1));
+        clock.start(&Piece::White);
+        assert!(!clock.isFlagged(&Piece::White));
+        thread::sleep(Duration::from_millis(20));
+        assert!(clock.isFlagged(&Piece::White));
+        assert!(!clock.isFlagged(&Piece::Black));
+        // 時間切れの後に打っても増加時間は加えない
+        assert!(!clock.punch());
+        assert_eq!(clock.getRemaining(&Piece::White), Duration::from_secs(0));
+    }
+
+    #[test]
+    fn allocate() {
+        assert_eq!(allocateTime(Duration::from_secs(64), Duration::from_secs(0), 60), Duration::from_secs(2));
+        assert_eq!(allocateTime(Duration::from_secs(64), Duration::from_secs(4), 60), Duration::from_secs(5));
+        // 残り時間の半分は超えない
+        assert_eq!(allocateTime(Duration::from_secs(2), Duration::from_secs(10), 60), Duration::from_secs(1));
+        assert_eq!(allocateTime(Duration::from_secs(3), Duration::from_secs(0), 0), Duration::from_secs(1));
+    }
+
+    #[test]
+    fn format() {
+        assert_eq!(formatDuration(Duration::from_millis(5500)), "5.5");
+        assert_eq!(formatDuration(Duration::from_secs(125)), "2:05");
+    }
+}
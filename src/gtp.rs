@@ -0,0 +1,400 @@
+#![allow(non_snake_case)]
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::AtomicBool;
+
+use crate::board::{isValidSize, Board, Piece, Pos, SearchStop, DEFAULT_SIZE};
+use crate::record::GameRecord;
+use crate::search;
+
+const COMMANDS: [&str; 14] = [
+    "protocol_version",
+    "name",
+    "version",
+    "known_command",
+    "list_commands",
+    "quit",
+    "boardsize",
+    "clear_board",
+    "komi",
+    "play",
+    "genmove",
+    "showboard",
+    "final_score",
+    "undo"
+];
+
+// "b", "black"などを駒にする
+fn parseColor(s: &str) -> Option<Piece> {
+    return match s.to_lowercase().as_str() {
+        "b" | "black" => Some(Piece::Black),
+        "w" | "white" => Some(Piece::White),
+        _ => None
+    };
+}
+
+// GTPの列の文字（'I'は使わないので、9列目以降はPos::getColumnNameと違う）
+const COLUMN_LETTERS: &str = "ABCDEFGHJKLMNOPQ";
+
+// 列の文字（1始まり）
+fn formatColumn(x: i32) -> String {
+    return COLUMN_LETTERS.chars().nth((x - 1) as usize).map_or(" ".to_string(), |c| c.to_string());
+}
+
+// GTPの行番号（下が1）と盤の行（上が1）を入れ替える
+fn flipRow(y: i32, size: i32) -> i32 {
+    return size + 1 - y;
+}
+
+// 一辺sizeの盤の"F5"または"pass"にする
+fn formatVertex(optPos: Option<Pos>, size: i32) -> String {
+    return match optPos {
+        Some(pos) => format!("{}{}", formatColumn(pos.x), flipRow(pos.y, size)),
+        None => "pass".to_string()
+    };
+}
+
+// 一辺sizeの盤の"f5"、"J10"または"pass"を読む．パスはSome(None)
+//
+// GTPでは行は下から数えるので、1行目は盤の一番下の行になる．盤の範囲外ならNone
+fn parseVertex(s: &str, size: i32) -> Option<Option<Pos>> {
+    if s.eq_ignore_ascii_case("pass") {
+        return Some(None);
+    }
+    let s = s.to_uppercase();
+    let mut chars = s.chars();
+    let x = COLUMN_LETTERS.find(chars.next()?)? as i32 + 1;
+    let rest = chars.as_str();
+    if !rest.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let row = rest.parse::<i32>().ok()?;
+    if (x > size) || (row < 1) || (row > size) {
+        return None;
+    }
+    return Some(Some(Pos { x, y: flipRow(row, size) }));
+}
+
+// Go Text Protocolのエンジン
+//
+// 局面は棋譜として持ち、現在の局面は棋譜を最後まで再生して求める．
+// playは手番に関係なく指定された色で打つ．
+pub struct GtpEngine {
+    pub depth: i32,
+    size: i32,  // boardsizeで指定された盤の一辺のマス数
+    record: GameRecord
+}
+
+impl GtpEngine {
+    pub fn new(depth: i32) -> Self {
+        let mut engine = GtpEngine {
+            depth,
+            size: DEFAULT_SIZE,
+            record: GameRecord::new(&Board::new(), &Piece::Black)
+        };
+        engine.clearBoard();
+        return engine;
+    }
+
+    fn clearBoard(&mut self) {
+        let mut board = Board::withSize(self.size);
+        board.init();
+        self.record = GameRecord::new(&board, &Piece::Black);
+    }
+
+    fn getBoard(&self) -> Board {
+        return match self.record.replay() {
+            Ok(steps) => steps.last().unwrap().board.clone(),
+            Err(_) => self.record.startBoard.clone()
+        };
+    }
+
+    // 1行の命令を処理する
+    //
+    // @return 応答（成功なら"= ..."、失敗なら"? ..."、空行で終わる）と、quitならtrue
+    pub fn handle(&mut self, line: &str) -> (String, bool) {
+        // #以降はコメント
+        let line = match line.find('#') {
+            Some(n) => &line[..n],
+            None => line
+        };
+        let mut words = line.split_whitespace().collect::<Vec<&str>>();
+        if words.len() == 0 {
+            return (String::new(), false);
+        }
+
+        // 先頭の数字は命令のID
+        let mut id = String::new();
+        if words[0].chars().all(|c| c.is_ascii_digit()) {
+            id = words.remove(0).to_string();
+            if words.len() == 0 {
+                return (format!("?{} missing command\n\n", id), false);
+            }
+        }
+
+        let command = words[0];
+        let args = &words[1..];
+        let result = self.execute(command, args);
+        let response = match result {
+            Ok(text) => format!("={} {}\n\n", id, text),
+            Err(text) => format!("?{} {}\n\n", id, text)
+        };
+        return (response, command == "quit");
+    }
+
+    fn execute(&mut self, command: &str, args: &[&str]) -> Result<String, String> {
+        match command {
+            "protocol_version" => {
+                return Ok("2".to_string());
+            },
+            "name" => {
+                return Ok("othello".to_string());
+            },
+            "version" => {
+                return Ok(env!("CARGO_PKG_VERSION").to_string());
+            },
+            "known_command" => {
+                let known = args.len() > 0 && COMMANDS.contains(&args[0]);
+                return Ok(known.to_string());
+            },
+            "list_commands" => {
+                return Ok(COMMANDS.join("\n"));
+            },
+            "quit" => {
+                return Ok(String::new());
+            },
+            "boardsize" => {
+                let size = args.get(0).and_then(|s| s.parse::<i32>().ok()).ok_or("syntax error")?;
+                if !isValidSize(size) {
+                    return Err("unacceptable size".to_string());
+                }
+                // 大きさを変えたら盤は空（初期配置）に戻す
+                self.size = size;
+                self.clearBoard();
+                return Ok(String::new());
+            },
+            "clear_board" => {
+                self.clearBoard();
+                return Ok(String::new());
+            },
+            "komi" => {
+                // コミはない
+                return Ok(String::new());
+            },
+            "play" => {
+                if args.len() < 2 {
+                    return Err("syntax error".to_string());
+                }
+                let piece = parseColor(args[0]).ok_or("syntax error")?;
+                let optPos = parseVertex(args[1], self.size).ok_or("syntax error")?;
+                let board = self.getBoard();
+                match optPos {
+                    Some(pos) => {
+                        if board.put(&piece, &pos).is_none() {
+                            return Err("illegal move".to_string());
+                        }
+                    },
+                    None => {
+                        if board.searchPos(&piece).len() > 0 {
+                            return Err("illegal move".to_string());
+                        }
+                    }
+                }
+                self.record.push(&piece, optPos);
+                return Ok(String::new());
+            },
+            "genmove" => {
+                let piece = args.get(0).and_then(|s| parseColor(s)).ok_or("syntax error")?;
+                let board = self.getBoard();
+                let optPos = search::search(&board, &piece, self.depth, &SearchStop::new(&AtomicBool::new(false))).getBestMove();
+                self.record.push(&piece, optPos);
+                return Ok(formatVertex(optPos, self.size));
+            },
+            "showboard" => {
+                return Ok(format!("\n{}", formatBoard(&self.getBoard())));
+            },
+            "final_score" => {
+                let count = self.getBoard().getCount();
+                let diff = count.nBlackPieces - count.nWhitePieces;
+                return Ok(if diff > 0 {
+                    format!("B+{}", diff)
+                } else if diff < 0 {
+                    format!("W+{}", -diff)
+                } else {
+                    "0".to_string()
+                });
+            },
+            "undo" => {
+                return match self.record.moves.pop() {
+                    Some(_) => Ok(String::new()),
+                    None => Err("cannot undo".to_string())
+                };
+            },
+            _ => {
+                return Err("unknown command".to_string());
+            }
+        }
+    }
+}
+
+// 盤をASCII文字で表す（黒がX、白がO）．GTPの行番号の大きい方から並べる
+fn formatBoard(board: &Board) -> String {
+    let size = board.getSize();
+    let w = if size > 9 { 2 } else { 1 };
+    let columns = (1..=size).map(formatColumn).collect::<Vec<String>>().join(" ");
+    let mut text = format!("{:w$} {}\n", "", columns, w = w);
+    for row in (1..=size).rev() {
+        text.push_str(&format!("{:>w$}", row, w = w));
+        for x in 1..=size {
+            text.push_str(match board.getPiece(x, flipRow(row, size)) {
+                Some(Piece::Black) => " X",
+                Some(Piece::White) => " O",
+                _ => " ."
+            });
+        }
+        text.push_str(&format!(" {}\n", row));
+    }
+    text.push_str(&format!("{:w$} {}", "", columns, w = w));
+    return text;
+}
+
+// 標準入出力などでGTPのエンジンとして動く
+pub fn run(input: &mut dyn BufRead, out: &mut dyn Write, depth: i32) -> std::io::Result<()> {
+    let mut engine = GtpEngine::new(depth);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            // 入力が閉じられた
+            break;
+        }
+        let (response, bQuit) = engine.handle(&line);
+        out.write_all(response.as_bytes())?;
+        out.flush()?;
+        if bQuit {
+            break;
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::board::MAX_SIZE;
+
+    // 命令を順に送り、応答を返す
+    fn runSession(commands: &[&str]) -> Vec<String> {
+        let mut engine = GtpEngine::new(2);
+        return commands.iter().map(|command| engine.handle(command).0).collect();
+    }
+
+    #[test]
+    fn session() {
+        let responses = runSession(&[
+            "1 protocol_version",
+            "name",
+            "known_command genmove",
+            "known_command foo",
+            "play b f4",
+            "play w f4",
+            "play w pass",
+            "2 play w d3",
+            "genmove b",
+            "undo",
+            "undo",
+            "final_score",
+            "foo",
+            "# comment",
+            "quit"
+        ]);
+        assert_eq!(responses, vec!(
+            "=1 2\n\n",
+            "= othello\n\n",
+            "= true\n\n",
+            "= false\n\n",
+            "= \n\n",
+            "? illegal move\n\n",
+            "? illegal move\n\n",
+            "=2 \n\n",
+            "= C3\n\n",
+            "= \n\n",
+            "= \n\n",
+            "= B+3\n\n",
+            "? unknown command\n\n",
+            "",
+            "= \n\n"
+        ));
+    }
+
+    #[test]
+    fn quit() {
+        let mut engine = GtpEngine::new(1);
+        assert_eq!(engine.handle("quit").1, true);
+        assert_eq!(engine.handle("name").1, false);
+    }
+
+    #[test]
+    fn boardsizeAndShowboard() {
+        let responses = runSession(&["boardsize 7", "boardsize 6", "play b b4", "showboard"]);
+        assert_eq!(responses[0], "? unacceptable size\n\n");
+        assert_eq!(responses[1], "= \n\n");
+        assert_eq!(responses[2], "= \n\n");
+        assert_eq!(responses[3], concat!(
+            "= \n",
+            "  A B C D E F\n",
+            "6 . . . . . . 6\n",
+            "5 . . . . . . 5\n",
+            "4 . X X X . . 4\n",
+            "3 . . X O . . 3\n",
+            "2 . . . . . . 2\n",
+            "1 . . . . . . 1\n",
+            "  A B C D E F\n\n"
+        ));
+    }
+
+    #[test]
+    fn vertices() {
+        // 1行目は一番下の行
+        assert_eq!(parseVertex("f5", 8), Some(Some(Pos { x: 6, y: 4 })));
+        assert_eq!(parseVertex("A1", 8), Some(Some(Pos { x: 1, y: 8 })));
+        assert_eq!(parseVertex("h8", 8), Some(Some(Pos { x: 8, y: 1 })));
+        assert_eq!(parseVertex("PASS", 8), Some(None));
+        assert_eq!(parseVertex("J10", 16), Some(Some(Pos { x: 9, y: 7 })));
+        assert_eq!(parseVertex("Q16", 16), Some(Some(Pos { x: 16, y: 1 })));
+        assert_eq!(parseVertex("I5", 16), None);
+        assert_eq!(parseVertex("R1", 16), None);
+        assert_eq!(parseVertex("A17", 16), None);
+        assert_eq!(parseVertex("A9", 8), None);
+        assert_eq!(parseVertex("J1", 8), None);
+        assert_eq!(parseVertex("A", 8), None);
+        assert_eq!(formatVertex(Some(Pos { x: 1, y: 1 }), 6), "A6");
+        assert_eq!(formatVertex(None, 6), "pass");
+        for y in 1..=MAX_SIZE {
+            for x in 1..=MAX_SIZE {
+                assert_eq!(parseVertex(&formatVertex(Some(Pos { x, y }), MAX_SIZE), MAX_SIZE), Some(Some(Pos { x, y })));
+            }
+        }
+    }
+
+    #[test]
+    fn largeBoard() {
+        let responses = runSession(&["boardsize 10", "play b d6", "showboard"]);
+        assert_eq!(responses[1], "= \n\n");
+        assert!(responses[2].starts_with("= \n   A B C D E F G H J K\n"), "{}", responses[2]);
+        assert!(responses[2].contains("\n 6 . . . X X X . . . . 6\n"), "{}", responses[2]);
+
+        // genmoveの手はそのままplayに使える
+        let mut engine = GtpEngine::new(2);
+        engine.handle("boardsize 16");
+        for piece in &["b", "w", "b", "w"] {
+            let response = engine.handle(&format!("genmove {}", piece)).0;
+            let vertex = response.trim_start_matches("= ").trim();
+            let mut other = GtpEngine::new(2);
+            other.size = engine.size;
+            other.record = engine.record.clone();
+            other.record.moves.pop();
+            assert_eq!(other.handle(&format!("play {} {}", piece, vertex)).0, "= \n\n");
+        }
+    }
+}
@@ -0,0 +1,120 @@
+#![allow(non_snake_case)]
+
+use std::time::Duration;
+
+use crate::board::{Board, Piece};
+use crate::player::{Player, EnginePlayer, Evaluation, MistakePlayer};
+use crate::random::Rng;
+
+// コンピュータの強さ
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Level {
+    pub name: &'static str,
+    pub depth: i32,                 // 読みの深さ
+    pub optTimeMillis: Option<u64>, // 1手に使う時間（Noneは深さまで読み切る）
+    pub bDiscOnly: bool,            // 場所の価値を考えず、駒の数だけで評価する
+    pub mistakeRate: f64            // わざと置ける場所からランダムに選ぶ確率
+}
+
+// 弱い順
+pub const LEVELS: [Level; 5] = [
+    Level { name: "beginner", depth: 1, optTimeMillis: None, bDiscOnly: true, mistakeRate: 0.3 },
+    Level { name: "easy", depth: 2, optTimeMillis: None, bDiscOnly: true, mistakeRate: 0.1 },
+    Level { name: "normal", depth: 4, optTimeMillis: Some(1000), bDiscOnly: false, mistakeRate: 0.05 },
+    Level { name: "hard", depth: 8, optTimeMillis: Some(3000), bDiscOnly: false, mistakeRate: 0.0 },
+    Level { name: "expert", depth: 13, optTimeMillis: Some(5000), bDiscOnly: false, mistakeRate: 0.0 }
+];
+
+pub fn findLevel(name: &str) -> Option<&'static Level> {
+    return LEVELS.iter().find(|level| level.name.eq_ignore_ascii_case(name));
+}
+
+impl Level {
+    pub fn createPlayer(&self) -> Box<dyn Player> {
+        return self.createPlayerWithSeed(Rng::fromTime().nextU64());
+    }
+
+    // createPlayerと同じだが、ランダムな手の選び方をseedで決める（同じseedなら同じ手を選ぶ）
+    pub fn createPlayerWithSeed(&self, seed: u64) -> Box<dyn Player> {
+        let mut engine = EnginePlayer::new(self.depth);
+        engine.optTime = self.optTimeMillis.map(Duration::from_millis);
+        engine.setSeed(seed);
+        if self.bDiscOnly {
+            engine.evaluation = Evaluation::Disc;
+        }
+        if self.mistakeRate > 0.0 {
+            return Box::new(MistakePlayer::new(Box::new(engine), self.mistakeRate, seed));
+        }
+        return Box::new(engine);
+    }
+}
+
+// 一辺sizeマスの盤でハンディキャップとして置く隅（置く順）
+fn getHandicapCorners(size: i32) -> [(i32, i32); 4] {
+    return [(1, 1), (size, size), (1, size), (size, 1)];
+}
+
+// pieceの駒をn個（最大4個）の隅に置く
+//
+// すでに駒がある隅は飛ばす．
+pub fn applyHandicap(board: &mut Board, piece: &Piece, n: usize) {
+    for &(x, y) in getHandicapCorners(board.getSize()).iter().take(n) {
+        if board.getPiece(x, y) == Some(&Piece::Space) {
+            board.setPiece(x, y, *piece);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn levels() {
+        assert_eq!(findLevel("normal").map(|l| l.depth), Some(4));
+        assert_eq!(findLevel("Expert").map(|l| l.name), Some("expert"));
+        assert_eq!(findLevel("master"), None);
+        assert_eq!(findLevel(""), None);
+
+        // 強いレベルほど深く読み、深く読むレベルは時間で打ち切る
+        for pair in LEVELS.windows(2) {
+            assert!(pair[0].depth < pair[1].depth);
+        }
+        for level in LEVELS.iter().filter(|l| l.depth >= 4) {
+            assert!(level.optTimeMillis.is_some(), "{}", level.name);
+        }
+    }
+
+    fn getCorners(board: &Board) -> Vec<Option<&Piece>> {
+        let size = board.getSize();
+        return getHandicapCorners(size).iter().map(|&(x, y)| board.getPiece(x, y)).collect();
+    }
+
+    #[test]
+    fn handicap() {
+        let mut board = Board::new();
+        board.init();
+        applyHandicap(&mut board, &Piece::Black, 0);
+        assert_eq!(getCorners(&board), vec!(Some(&Piece::Space); 4));
+
+        applyHandicap(&mut board, &Piece::Black, 2);
+        assert_eq!(board.getPiece(1, 1), Some(&Piece::Black));
+        assert_eq!(board.getPiece(8, 8), Some(&Piece::Black));
+        assert_eq!(board.getPiece(1, 8), Some(&Piece::Space));
+        assert_eq!(board.getPiece(8, 1), Some(&Piece::Space));
+
+        // 4個より多くは置かない
+        let mut board = Board::withSize(6);
+        board.init();
+        applyHandicap(&mut board, &Piece::White, 10);
+        assert_eq!(getCorners(&board), vec!(Some(&Piece::White); 4));
+        assert_eq!(board.getCount().nWhitePieces, 2 + 4);
+
+        // 駒がある隅はそのまま
+        let mut board = Board::new();
+        board.init();
+        board.setPiece(8, 8, Piece::White);
+        applyHandicap(&mut board, &Piece::Black, 3);
+        assert_eq!(getCorners(&board), vec!(Some(&Piece::Black), Some(&Piece::White), Some(&Piece::Black), Some(&Piece::Space)));
+    }
+}
@@ -1,2 +1,16 @@
 pub mod board;
-pub mod Util;
\ No newline at end of file
+pub mod Util;
+pub mod random;
+pub mod mcts;
+pub mod player;
+pub mod theme;
+pub mod record;
+pub mod analysis;
+pub mod arena;
+pub mod nboard;
+pub mod gtp;
+pub mod clock;
+pub mod net;
+pub mod level;
+pub mod search;
+pub mod logging;
\ No newline at end of file
@@ -0,0 +1,17 @@
+#![allow(non_snake_case)]
+
+// ライブラリ内からのログ
+//
+// "log" featureを有効にするとlogクレートに流すので、使う側で好きなloggerを設定できる．
+// 無効なら何もしない（標準出力には書かない）．
+// メッセージは必要になったときだけ作るようにクロージャで渡す．
+
+#[cfg(feature = "log")]
+pub fn debug(message: impl FnOnce() -> String) {
+    if log::log_enabled!(log::Level::Debug) {
+        log::debug!("{}", message());
+    }
+}
+
+#[cfg(not(feature = "log"))]
+pub fn debug(_message: impl FnOnce() -> String) {}
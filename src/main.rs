@@ -7,18 +7,34 @@ use sdl2::keyboard::Keycode;
 use sdl2::rect::Rect;
 use sdl2::image::LoadTexture;
 use sdl2::video::{Window, WindowContext};
-use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::render::{BlendMode, Canvas, Texture, TextureCreator};
 use sdl2::ttf::Font;
 
 use std::io::*;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
 use std::thread;
-use std::time;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
+use othello::analysis;
+use othello::arena;
 use othello::board;
+use othello::clock;
+use othello::gtp;
+use othello::mcts;
+use othello::nboard;
+use othello::net;
+use othello::level;
+use othello::player::{Player, Move};
+use othello::record;
+use othello::theme::{findAsset, Theme};
 use othello::Util;
 
-fn getUserInput(piece: &board::Piece) -> Option<board::Pos> {
+// 打つ位置を標準入力から読む
+//
+// @return 入力が閉じられたらNone、読めない位置ならSome(None)
+fn getUserInput(piece: &board::Piece) -> Option<Option<board::Pos>> {
     match piece {
         board::Piece::White => {
             print!("● ");
@@ -34,41 +50,15 @@ fn getUserInput(piece: &board::Piece) -> Option<board::Pos> {
     stdout().flush().unwrap();
 
     let mut line = String::new();
-    if stdin().read_line(&mut line).is_err() {
-        return None;
-    }
-
-    line = line.trim().to_uppercase().to_string();
-
-    let first = line.chars().nth(0);
-    if first.is_none() {
-        return None;
-    }
-    let x = match first.unwrap() {
-        'A' => 1,
-        'B' => 2,
-        'C' => 3,
-        'D' => 4,
-        'E' => 5,
-        'F' => 6,
-        'G' => 7,
-        'H' => 8,
-        _ => 0
-    };
-    if x == 0 {
-        return None;
+    match stdin().read_line(&mut line) {
+        Ok(0) | Err(_) => {
+            return None;
+        },
+        Ok(_) => {}
     }
 
-    let second = line.chars().nth(1);
-    if second.is_none() {
-        return None;
-    }
-    if let Some(y) = second.unwrap().to_string().parse::<i32>().ok() {
-        if (y >= 1) && (y <= 8) {
-            return Some(board::Pos { x, y });
-        }
-    }
-    return None;
+    // 盤の外の位置は置けない手として扱われる
+    return Some(board::Pos::fromDesc(&line));
 }
 
 fn test00(optBoardPath: Option<String>) {
@@ -84,7 +74,7 @@ fn test00(optBoardPath: Option<String>) {
         board.init();
     }
     
-    board.print();
+    println!("{}", board);
 
     println!("** White **");
     let result = board.searchPos(&board::Piece::White);
@@ -112,13 +102,13 @@ fn test01(optBoardPath: Option<String>) {
         board.init();
     }
     
-    board.print();
+    println!("{}", board);
 
     let mut c=0;
     let nextBoards = board.genNextBoards(&board::Piece::White);
     for nextBoard in &nextBoards {
         println!("[{}] ({}, {}) : ntake={}, score={}", c, nextBoard.pos.x, nextBoard.pos.y, nextBoard.ntake, nextBoard.score);
-        nextBoard.board.print();
+        println!("{}", nextBoard.board);
         c=c+1;
     }
 }
@@ -136,7 +126,7 @@ fn test02(optBoardPath: Option<String>) {
         board.init();
     }
     
-    board.print();
+    println!("{}", board);
 
     let tree = board.genSearchTree(&board::Piece::White, 1);
     for elem in &tree {
@@ -152,7 +142,7 @@ fn test02(optBoardPath: Option<String>) {
         println!();
 
         println!("score: {}", elem.score);
-        elem.board.print();
+        println!("{}", elem.board);
     }
 }
 
@@ -168,7 +158,7 @@ fn test03(optBoardPath: Option<String>) {
         board.init();
     }
     
-    board.print();
+    println!("{}", board);
 
     let maybeResult = board.getBestMove(&board::Piece::White, 3);
     if let Some(result) = maybeResult {
@@ -184,7 +174,7 @@ fn test03(optBoardPath: Option<String>) {
         println!();
     
         println!("score: {}", result.score);
-        result.board.print();
+        println!("{}", result.board);
     } else {
         println!("no result found");
     }
@@ -200,235 +190,2390 @@ fn test04(optBoardPath: Option<String>) {
         }
     }
 
-    board.print();
+    println!("{}", board);
 }
 
-fn drawBoard<'a>(
+// minimaxとMCTSの選ぶ手を比べる
+fn test05(optBoardPath: Option<String>) {
+    let mut board = board::Board::new();
+
+    if let Some(boardPath) = optBoardPath {
+        if !board.load(&boardPath) {
+            println!("failed to load {}", boardPath);
+            return;
+        }
+    } else {
+        board.init();
+    }
+
+    println!("{}", board);
+
+    let piece = board::Piece::White;
+    if let Some(result) = board.getBestMove(&piece, 5) {
+        if result.path.len() > 0 {
+            let p = &result.path[0].pos;
+            println!("minimax: {} (score: {})", board::Pos::toDesc(p.x, p.y), result.score);
+        }
+    }
+
+    let mut engine = mcts::Mcts::new(mcts::MctsLimit::Iterations(10000));
+    let optPos = engine.search(&board, &piece);
+    println!("mcts: {} playouts", engine.getTotalVisits());
+    for stat in &engine.getMoveStats() {
+        let desc = match &stat.pos {
+            Some(p) => board::Pos::toDesc(p.x, p.y),
+            None => "pass".to_string()
+        };
+        println!("  {} visits={} win={:.3}", desc, stat.visits, stat.winRate);
+    }
+    if let Some(p) = optPos {
+        println!("mcts: {}", board::Pos::toDesc(p.x, p.y));
+    }
+}
+
+// 盤の上に重ねて表示するもの
+struct Overlay {
+    hints: Vec<board::Pos>,             // 置ける場所
+    lastMove: Option<board::Pos>,       // 最後に打たれた場所
+    evals: Vec<(board::Pos, i32)>,      // 置ける場所ごとの評価値
+    optCursor: Option<board::Pos>,      // キーボードのカーソル
+    flips: Vec<board::Pos>              // 直前の手で裏返った場所（棋譜の再生用）
+}
+
+impl Overlay {
+    fn new() -> Self {
+        return Overlay {
+            hints: vec!(),
+            lastMove: None,
+            evals: vec!(),
+            optCursor: None,
+            flips: vec!()
+        };
+    }
+}
+
+// 駒を置いて、取った駒を裏返すアニメーション
+struct Animation {
+    pos: board::Pos,                // 置いた場所
+    piece: board::Piece,            // 置いた駒
+    captured: Vec<board::Pos>,      // 裏返す駒の場所
+    frame: u32,
+    nFrames: u32                    // 置く、裏返すのそれぞれにかけるフレーム数
+}
+
+impl Animation {
+    fn new(ret: &board::SearchResult2, nFrames: u32) -> Self {
+        return Animation {
+            pos: ret.pos,
+            piece: ret.piece,
+            captured: ret.capturedPieceLocs.clone(),
+            frame: 0,
+            nFrames
+        };
+    }
+
+    fn isFinished(&self) -> bool {
+        return self.frame >= self.nFrames * 2;
+    }
+
+    // 最後まで飛ばす
+    fn skip(&mut self) {
+        self.frame = self.nFrames * 2;
+    }
+
+    // (x, y)に描く駒と、その幅・高さの比率を返す（アニメーションしない場所はNone）
+    fn getPieceScale(&self, x: i32, y: i32) -> Option<(board::Piece, f64, f64)> {
+        let n = self.nFrames.max(1) as f64;
+        if (self.pos.x == x) && (self.pos.y == y) {
+            // 置いた駒は小さい状態から大きくする
+            let t = (self.frame as f64 / n).min(1.0);
+            return Some((self.piece, t, t));
+        }
+        if self.captured.iter().any(|p| (p.x == x) && (p.y == y)) {
+            // 前半は相手の駒を細くし、後半は自分の駒を太くする
+            let t = ((self.frame as f64 - n) / n).max(0.0).min(1.0);
+            if t < 0.5 {
+                return Some((board::Piece::getOpponent(&self.piece), 1.0 - t * 2.0, 1.0));
+            } else {
+                return Some((self.piece, t * 2.0 - 1.0, 1.0));
+            }
+        }
+        return None;
+    }
+}
+
+// 盤の右側に表示する状態
+struct Status {
+    lastMove: String,   // 最後に打たれた手
+    eval: String,       // エンジンの評価
+    clock: String,      // 黒と白の残り時間（時計を使わなければ空）
+    message: String     // パスや終局などの知らせ
+}
+
+impl Status {
+    fn new() -> Self {
+        return Status {
+            lastMove: "-".to_string(),
+            eval: "-".to_string(),
+            clock: String::new(),
+            message: String::new()
+        };
+    }
+}
+
+// 終局時の結果の文字列
+fn getResultText(board: &board::Board) -> String {
+    let count = board.getCount();
+    if count.nBlackPieces > count.nWhitePieces {
+        return format!("Black wins {}-{}", count.nBlackPieces, count.nWhitePieces);
+    } else if count.nWhitePieces > count.nBlackPieces {
+        return format!("White wins {}-{}", count.nWhitePieces, count.nBlackPieces);
+    } else {
+        return format!("Draw {}-{}", count.nBlackPieces, count.nWhitePieces);
+    }
+}
+
+// 黒と白の残り時間の文字列
+fn getClockText(black: Duration, white: Duration) -> String {
+    return format!("{} / {}", clock::formatDuration(black), clock::formatDuration(white));
+}
+
+// 状態表示の欄を描く
+fn drawStatus<'a>(
     canvas: &mut Canvas<Window>,
-    texture: &Texture,
     font: &Font,
     texture_creator: &'a TextureCreator<WindowContext>,
-    board: &board::Board) -> () 
+    layout: &Layout,
+    theme: &Theme,
+    board: &board::Board,
+    turnText: &str,
+    status: &Status) -> ()
 {
-    let lm = 32;    // left margin
-    let tm = 32;    // top margin
-    let ps = 96;    // piece size
+    let tc = theme.textColor;
+    let mc = theme.messageColor;
+    let x = layout.panelX + layout.margin;
+    let wl = (layout.ts / 8) as u32;        // font width
+    let hl = (layout.ts * 5 / 24) as u32;   // font height
+    let count = board.getCount();
 
-    for _y in 1..=8 {
-        for _x in 1..=8 {
-            let optPiece = board.getPiece(_x, _y);
-            if let Some(piece) = optPiece {
-                let src: Rect;
-                match piece {
-                    &board::Piece::White => {
-                        src = Rect::new(192, 0, 96, 96);
-                    },
-                    &board::Piece::Black => {
-                        src = Rect::new(96, 0, 96, 96);
-                    },
-                    &board::Piece::Space => {
-                        src = Rect::new(0, 0, 96, 96);
-                    }
-                }
-                let dest: Rect = Rect::new((_x-1)*96+lm, (_y-1)*96+tm, 96, 96);
-                canvas.copy(texture, Some(src), Some(dest)).expect("copy texture to canvas failed");
-            }
+    let lines = [
+        format!("Black : {}", count.nBlackPieces),
+        format!("White : {}", count.nWhitePieces),
+        String::new(),
+        format!("Turn  : {}", turnText),
+        format!("Last  : {}", status.lastMove),
+        format!("Eval  : {}", status.eval),
+        if status.clock.len() > 0 { format!("Clock : {}", status.clock) } else { String::new() }
+    ];
+    for i in 0..lines.len() {
+        if lines[i].len() > 0 {
+            Util::textOut(canvas, &font, &texture_creator, &lines[i], tc.r, tc.g, tc.b, x, layout.getStatusLineY(i as i32), wl, hl);
         }
     }
+    if status.message.len() > 0 {
+        Util::textOut(canvas, &font, &texture_creator, &status.message, mc.r, mc.g, mc.b, x, layout.getStatusLineY(7), wl, hl);
+    }
+}
+
+// 駒の画像の範囲
+fn getPieceSrcRect(piece: &board::Piece) -> Rect {
+    return match piece {
+        board::Piece::White => Rect::new(192, 0, 96, 96),
+        board::Piece::Black => Rect::new(96, 0, 96, 96),
+        board::Piece::Space => Rect::new(0, 0, 96, 96)
+    };
+}
+
+// 盤と状態表示欄の配置（ウィンドウの大きさから計算する）
+//
+// 描画は描画領域のピクセル単位、マウスの座標はウィンドウの座標単位なので、
+// high-DPIの画面ではscaleで換算する．
+#[derive(Copy, Clone, Debug, PartialEq)]
+struct Layout {
+    n: i32,         // 盤の一辺のマス数
+    lm: i32,        // left margin
+    tm: i32,        // top margin
+    ps: i32,        // piece size
+    ts: i32,        // 文字の大きさの基準（盤の幅の1/8．盤の大きさによらない）
+    margin: i32,    // 座標ラベル用の余白
+    panelX: i32,    // 状態表示欄の左端
+    scale: f64      // ウィンドウ座標 → 描画座標
+}
+
+impl Layout {
+    // 描画領域の大きさ(outputW, outputH)とウィンドウの幅windowW、盤の一辺のマス数nから計算する
+    fn new(outputW: u32, outputH: u32, windowW: u32, n: i32) -> Self {
+        let w = outputW as i32;
+        let h = outputH as i32;
+        let margin = (w.min(h) / 26).max(16);
+        let panelW = margin * 11;  // 状態表示欄の幅
+        let ps = ((h - margin * 2) / n).min((w - margin * 2 - panelW) / n).max(8);
+        let lm = margin;
+        let tm = ((h - ps * n) / 2).max(margin);
+        let scale = if windowW > 0 { (outputW as f64) / (windowW as f64) } else { 1.0 };
+
+        return Layout {
+            n,
+            lm,
+            tm,
+            ps,
+            ts: (ps * n / 8).max(8),
+            margin,
+            panelX: lm + ps * n + margin,
+            scale
+        };
+    }
 
-    let wl = 16;    // font width
-    let hl = 24;    // font height
-    let xChars = ["A", "B", "C", "D", "E", "F", "G", "H"];
-    for i in 0..xChars.len() {
-        let x = (i*ps + (ps/2)) as i32 + lm - 2;
-        Util::textOut(canvas, &font, &texture_creator, xChars[i], 255, 255, 255, x, 5, wl, hl);
-        Util::textOut(canvas, &font, &texture_creator, xChars[i], 255, 255, 255, x, (ps as i32) * 8 + (tm as i32) + 2, wl, hl);
+    fn fromCanvas(canvas: &Canvas<Window>, n: i32) -> Self {
+        let (windowW, windowH) = canvas.window().size();
+        let (outputW, outputH) = canvas.output_size().unwrap_or((windowW, windowH));
+        return Layout::new(outputW, outputH, windowW, n);
     }
-    for i in 0..8 {
-        let y = (i*ps + (ps/2)) as i32 + tm - 4;
-        Util::textOut(canvas, &font, &texture_creator, &((i+1) as i32).to_string(), 255, 255, 255, 8, y, wl, hl);
-        Util::textOut(canvas, &font, &texture_creator, &((i+1) as i32).to_string(), 255, 255, 255, 8 + (ps as i32) * 8 + (tm - 2) as i32, y, wl, hl);
+
+    // (x, y)のマスの描画範囲
+    fn getSquareRect(&self, x: i32, y: i32) -> Rect {
+        return Rect::new((x-1)*self.ps + self.lm, (y-1)*self.ps + self.tm, self.ps as u32, self.ps as u32);
     }
 
-    canvas.present();
-}
+    // ウィンドウ座標(x, y)にあるマスを返す
+    fn getPosAt(&self, x: i32, y: i32) -> Option<board::Pos> {
+        let px = ((x as f64) * self.scale) as i32 - self.lm;
+        let py = ((y as f64) * self.scale) as i32 - self.tm;
 
-fn getPosOnMouseClick(x: i32, y: i32) -> Option<board::Pos> {
-    let lm = 32;    // left margin
-    let tm = 32;    // top margin
-    let ps = 96;    // piece size
+        if (px < 0) || (py < 0) {
+            return None;
+        }
 
-    let px = x - lm;
-    let py = y - tm;
-    
-    if (px < 0) || (py < 0) {
-        return None;
+        let ix = px / self.ps;
+        let iy = py / self.ps;
+
+        if (ix >= self.n) || (iy >= self.n) {
+            return None;
+        }
+
+        return Some(board::Pos {x: ix+1, y: iy+1});
     }
 
-    let ix = px / ps;
-    let iy = py / ps;
+    // 状態表示欄のi行目のy座標
+    fn getStatusLineY(&self, i: i32) -> i32 {
+        return self.tm + self.margin / 2 + i * (self.ts * 3 / 8);
+    }
 
-    if (ix > 7) || (iy > 7) {
-        return None;
+    // 開始画面のi行目のy座標
+    fn getStartRowY(&self, i: i32) -> i32 {
+        return self.margin * 2 + self.ts * 2 + i * (self.ts * 5 / 4);
     }
 
-    return Some(board::Pos {x: ix+1, y: iy+1});
+    // ウィンドウ座標のyにある開始画面の行（nRows行のうち）
+    fn getStartRowAt(&self, y: i32, nRows: usize) -> Option<usize> {
+        let py = ((y as f64) * self.scale) as i32;
+        return (0..nRows).find(|&i| {
+            let ry = self.getStartRowY(i as i32);
+            (py >= ry - self.ts * 3 / 8) && (py < ry + self.ts * 7 / 8)
+        });
+    }
 }
 
-fn game(optBoardPath: Option<String>) {
-    let VERSION = 0.3;
-    let title = format!("*** Othello (ver {}) ***", VERSION);
-    
-    let sdl2_context = sdl2::init().unwrap();
-    let video_subsystem = sdl2_context.video().unwrap();
+// マス（空白）を描く．画像がなければ色で塗る
+fn drawSquare(canvas: &mut Canvas<Window>, optTexture: Option<&Texture>, theme: &Theme, dest: Rect) {
+    if let Some(texture) = optTexture {
+        canvas.copy(texture, Some(getPieceSrcRect(&board::Piece::Space)), Some(dest)).expect("copy texture to canvas failed");
+    } else {
+        canvas.set_draw_color(theme.boardColor);
+        canvas.fill_rect(dest).expect("fill rect failed");
+        canvas.set_draw_color(theme.gridColor);
+        canvas.draw_rect(dest).expect("draw rect failed");
+    }
+}
 
-    let width: u32 = 960;
-    let height: u32 = 832;
-    let window = video_subsystem
-        .window(&title, width, height)
-        .position_centered()
-        .build()
-        .unwrap();
+// destに駒を描く．画像がなければ楕円で描く
+//
+// 画像の場合は背景も含めて描くので、destが縮んでいるときは先にdrawSquareを呼んでおく
+fn drawDisc(canvas: &mut Canvas<Window>, optTexture: Option<&Texture>, theme: &Theme, piece: &board::Piece, dest: Rect) {
+    if let Some(texture) = optTexture {
+        canvas.copy(texture, Some(getPieceSrcRect(piece)), Some(dest)).expect("copy texture to canvas failed");
+        return;
+    }
 
-    let mut canvas = window.into_canvas().build().unwrap();
+    let c = match piece {
+        board::Piece::Black => theme.blackColor,
+        board::Piece::White => theme.whiteColor,
+        board::Piece::Space => {
+            return;
+        }
+    };
+    // マスの線に重ならないよう少し小さくする
+    let pad = ((dest.width().min(dest.height()) / 10) as i32).max(1);
+    let w = (dest.width() as i32 - pad * 2).max(1) as u32;
+    let h = (dest.height() as i32 - pad * 2).max(1) as u32;
+    Util::fillEllipse(canvas, Rect::new(dest.x() + pad, dest.y() + pad, w, h), c.r, c.g, c.b);
+}
 
-    let texture_creator: TextureCreator<_> = canvas.texture_creator();
-    let image_texture = texture_creator.load_texture("assets/Image1.png").expect("load image failed");
+fn drawBoard<'a>(
+    canvas: &mut Canvas<Window>,
+    optTexture: Option<&Texture>,
+    font: &Font,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    layout: &Layout,
+    theme: &Theme,
+    board: &board::Board,
+    overlay: &Overlay,
+    optAnim: Option<&Animation>) -> () 
+{
+    let lm = layout.lm;
+    let tm = layout.tm;
+    let ps = layout.ps;
 
-    // init font stuff
-    let ttf_context = sdl2::ttf::init().expect("failed to init SDL TTF");
-    let font = ttf_context.load_font("assets/arial.ttf", 128).expect("failed to load font");
-    
-    // clear canvas
-    canvas.set_draw_color(Color::RGB(0, 0, 0)); // black
+    canvas.set_draw_color(theme.background);
     canvas.clear();
-    canvas.present();
-    
-    println!("***************************");
-    println!("  Othello Game (ver {})", VERSION);
-    println!("***************************");
 
-    let mut board = board::Board::new();
+    let n = board.getSize();
+    for _y in 1..=n {
+        for _x in 1..=n {
+            let optPiece = board.getPiece(_x, _y);
+            if let Some(piece) = optPiece {
+                let dest = layout.getSquareRect(_x, _y);
+                if let Some((animPiece, sx, sy)) = optAnim.and_then(|a| a.getPieceScale(_x, _y)) {
+                    // アニメーション中の駒は空白の上に縮めて描く
+                    drawSquare(canvas, optTexture, theme, dest);
+                    let w = (((ps as f64) * sx) as u32).max(1);
+                    let h = (((ps as f64) * sy) as u32).max(1);
+                    let scaled = Rect::new(dest.x() + (ps - w as i32)/2, dest.y() + (ps - h as i32)/2, w, h);
+                    drawDisc(canvas, optTexture, theme, &animPiece, scaled);
+                    continue;
+                }
+                drawSquare(canvas, optTexture, theme, dest);
+                if *piece != board::Piece::Space {
+                    drawDisc(canvas, optTexture, theme, piece, dest);
+                }
+            }
+        }
+    }
+
+    // 置ける場所に印をつける
+    canvas.set_blend_mode(BlendMode::Blend);
+    canvas.set_draw_color(theme.hintColor);
+    for pos in &overlay.hints {
+        if overlay.evals.iter().any(|e| e.0 == *pos) {
+            continue;   // 評価値を表示するので印は不要
+        }
+        let center = layout.getSquareRect(pos.x, pos.y).center();
+        let size = ps / 4;
+        canvas.fill_rect(Rect::new(center.x() - size/2, center.y() - size/2, size as u32, size as u32)).expect("fill rect failed");
+    }
+    canvas.set_blend_mode(BlendMode::None);
+
+    // 最後に打たれた場所を枠で囲む
+    if let Some(pos) = &overlay.lastMove {
+        canvas.set_draw_color(theme.lastMoveColor);
+        let r = layout.getSquareRect(pos.x, pos.y);
+        for i in 0..(ps / 32).max(1) {
+            let frame = Rect::new(r.x() + i, r.y() + i, (ps - i*2) as u32, (ps - i*2) as u32);
+            canvas.draw_rect(frame).expect("draw rect failed");
+        }
+    }
+
+    // 裏返った場所に小さな印をつける
+    canvas.set_draw_color(theme.lastMoveColor);
+    for pos in &overlay.flips {
+        let r = layout.getSquareRect(pos.x, pos.y);
+        let size = (ps / 8).max(2);
+        canvas.fill_rect(Rect::new(r.x() + size / 2, r.y() + size / 2, size as u32, size as u32)).expect("fill rect failed");
+    }
+
+    // カーソル
+    if let Some(pos) = &overlay.optCursor {
+        canvas.set_draw_color(theme.cursorColor);
+        let r = layout.getSquareRect(pos.x, pos.y);
+        let inset = ps / 16;
+        for i in 0..(ps / 32).max(1) {
+            let frame = Rect::new(r.x() + inset + i, r.y() + inset + i, (ps - (inset + i)*2) as u32, (ps - (inset + i)*2) as u32);
+            canvas.draw_rect(frame).expect("draw rect failed");
+        }
+    }
+
+    // 評価値
+    let we = (ps / 8) as u32;       // font width
+    let he = (ps * 3 / 16) as u32;  // font height
+    for (pos, value) in &overlay.evals {
+        let text = value.to_string();
+        let center = layout.getSquareRect(pos.x, pos.y).center();
+        let x = center.x() - (text.len() as i32) * (we as i32) / 2;
+        let y = center.y() - (he as i32) / 2;
+        Util::textOut(canvas, &font, &texture_creator, &text, theme.messageColor.r, theme.messageColor.g, theme.messageColor.b, x, y, we, he);
+    }
+
+    if !theme.showLabels {
+        return;
+    }
+
+    let tc = theme.textColor;
+    let wl = (ps / 6) as u32;   // font width
+    let hl = (ps / 4) as u32;   // font height
+    for i in 0..n {
+        let xChar = board::Pos::getColumnName(i + 1);
+        let x = i*ps + (ps/2) + lm - (wl as i32)/8;
+        Util::textOut(canvas, &font, &texture_creator, &xChar, tc.r, tc.g, tc.b, x, tm - (hl as i32) - 3, wl, hl);
+        Util::textOut(canvas, &font, &texture_creator, &xChar, tc.r, tc.g, tc.b, x, ps * n + tm + 2, wl, hl);
+    }
+    for i in 0..n {
+        let y = i*ps + (ps/2) + tm - (hl as i32)/6;
+        Util::textOut(canvas, &font, &texture_creator, &(i+1).to_string(), tc.r, tc.g, tc.b, lm - layout.margin + 8, y, wl, hl);
+        Util::textOut(canvas, &font, &texture_creator, &(i+1).to_string(), tc.r, tc.g, tc.b, lm + ps * n + layout.margin / 5, y, wl, hl);
+    }
+}
+
+// boardPathの盤を読み込む（大きさはファイルに従う）．なければ一辺sizeマスの初期状態にする
+fn loadBoard(optBoardPath: &Option<String>, size: i32) -> Option<board::Board> {
+    let mut board = board::Board::withSize(size);
 
     if let Some(boardPath) = optBoardPath {
-        if !board.load(&boardPath) {
+        if !board.load(boardPath) {
             println!("failed to load {}", boardPath);
-            return;
+            return None;
         }
     } else {
-        // なければ初期状態にする
         board.init();
     }
 
-    board.print();
+    return Some(board);
+}
 
-    let playerPiece = &board::Piece::Black;
-    let computerPiece = board::Piece::getOpponent(&playerPiece);
-    let mut playerPass = false;
-    let mut computerPass = false;
-    let mut bFirst = true;
+// コンソールから入力する人間のプレイヤー
+struct TerminalPlayer;
 
-    let mut event_pump = sdl2_context.event_pump().unwrap();
-    'running: loop {
-        let mut playerInput: Option<board::Pos> = None;
-        for event in event_pump.poll_iter() {
-            match event {
-                Event::Quit { .. }
-                | Event::KeyDown {
-                    keycode: Some(Keycode::Escape), ..
-                } => break 'running,
-                | Event::MouseButtonDown {
-                    x, y, ..
-                } => {
-                    // println!("(x,y)=({},{})", x, y);
-                    playerInput = getPosOnMouseClick(x, y);
+impl Player for TerminalPlayer {
+    fn getName(&self) -> String {
+        return "human".to_string();
+    }
+
+    fn isHuman(&self) -> bool {
+        return true;
+    }
+
+    // 入力が閉じられたらNone（それ以上は打てない）
+    fn getMove(&mut self, _board: &board::Board, piece: &board::Piece) -> Option<Move> {
+        loop {
+            match getUserInput(piece) {
+                Some(Some(pos)) => {
+                    return Some(Move::Put(pos));
                 },
-                _ => {}
+                Some(None) => {},
+                None => {
+                    return None;
+                }
             }
         }
+    }
+}
 
-        drawBoard(&mut canvas, &image_texture, &font, &texture_creator, &board);
-        
-        if (!playerPass) || (!computerPass) {
-            // Human
-            
-            // 先に置ける場所があるかチェックする
-            let possibleMoves = board.searchPos(&playerPiece);
-            if possibleMoves.len() > 0 {
-                // let playerInput = getUserInput(&playerPiece);
-                if playerInput.is_none() {
-                    continue;
-                }
-                let playerPos = playerInput.unwrap();
-        
-                println!("({}, {})", playerPos.x, playerPos.y);
-        
-                if let Some(ret) = board.put(&playerPiece, &playerPos) {
-                    board = ret.board;  // 新しい盤に更新
-                    board.print();
-                    board.printScore();
-                    drawBoard(&mut canvas, &image_texture, &font, &texture_creator, &board);
-                    playerPass = false;
-                } else {
-                    println!("You cannot place on {}", board::Pos::toDesc(playerPos.x, playerPos.y));
-                    continue;
-                }    
-            } else {
-                println!("Sorry. No place for your piece.");
-                playerPass = true;
-            }
-    
-            // Computer
-            println!("Hmm ... ");
-            stdout().flush().unwrap();
-            // for _ in 0..6 {
-            //     thread::sleep(time::Duration::from_secs_f64(0.5));
-            //     print!(".");
-            //     stdout().flush().unwrap();
-            // }
-            // println!();
-
-            let maybeResult = board.getBestMove(&computerPiece, 13);
-            if let Some(result) = maybeResult {
-                if result.path.len() > 0 {
-                    let nextPos = &result.path[0].pos;
-                    if let Some(ret) = board.put(&computerPiece, nextPos) {
-                        board = ret.board;
-                        board.print();
-                        println!("I put on {}", board::Pos::toDesc(nextPos.x, nextPos.y));
-                        board.printScore();
-                        drawBoard(&mut canvas, &image_texture, &font, &texture_creator, &board);
-                    }
-                } else {
-                    println!("No place for me.");
-                    computerPass = true;
-                }
+// ウィンドウのクリックで入力する人間のプレイヤー
+struct MousePlayer {
+    input: Option<board::Pos>
+}
+
+impl Player for MousePlayer {
+    fn getName(&self) -> String {
+        return "human".to_string();
+    }
+
+    fn getMove(&mut self, _board: &board::Board, _piece: &board::Piece) -> Option<Move> {
+        return self.input.take().map(Move::Put);
+    }
+
+    fn setInput(&mut self, pos: &board::Pos) {
+        self.input = Some(*pos);
+    }
+
+    fn isHuman(&self) -> bool {
+        return true;
+    }
+}
+
+// 名前からプレイヤーを作る
+//
+// human, random, greedy, engine[:depth], mcts[:iterations], level:NAME
+fn createPlayer(name: &str, bConsole: bool) -> Option<Box<dyn Player>> {
+    if name == "human" {
+        if bConsole {
+            return Some(Box::new(TerminalPlayer));
+        } else {
+            return Some(Box::new(MousePlayer { input: None }));
+        }
+    }
+
+    return match arena::createPlayer(name) {
+        Ok(player) => Some(player),
+        Err(e) => {
+            println!("{}", e);
+            None
+        }
+    };
+}
+
+// 手を盤に反映する
+//
+// @return 盤が進んだらputの結果（アニメーション用）、入力待ちや置けない位置だった場合はNone
+fn applyMove(board: &mut board::Board, piece: &board::Piece, name: &str, optMove: Option<Move>) -> Option<board::SearchResult2> {
+    match optMove {
+        Some(Move::Put(pos)) => {
+            if let Some(ret) = board.put(piece, &pos) {
+                *board = ret.board.clone();  // 新しい盤に更新
+                println!("{}", board);
+                println!("{} ({}) put on {}", name, pieceMark(piece), board::Pos::toDesc(pos.x, pos.y));
+                println!("{}", board.getCount());
+                return Some(ret);
             } else {
-                println!("No place for me.");
-                computerPass = true;
+                println!("You cannot place on {}", board::Pos::toDesc(pos.x, pos.y));
             }
-        } else {
-            if bFirst {
-                println!("*** Game Over ***");
-                board.printScore();
-                bFirst = false; // ここへはもう来ない
+        },
+        Some(Move::Pass) => {
+            println!("You cannot pass while there is a place for your piece.");
+        },
+        None => {
+            // 入力待ち
+        }
+    }
+    return None;
+}
+
+// GUIでの対局の状態
+enum GameState {
+    AwaitingHuman,      // 人間の入力待ち
+    Thinking(Receiver<(Box<dyn Player>, Option<Move>)>),  // エンジンが別スレッドで考えている
+    Animating(Animation),   // 打たれた手のアニメーション中
+    Passed(u32),        // 手番の側が置けないのでパスする（残りの表示フレーム数）
+    GameOver            // 双方置けない
+}
+
+// パスの知らせを表示するフレーム数
+const PASS_FRAMES: u32 = 60;
+
+// turnの手番になったときの状態を決める
+//
+// 置ける場所があれば人間の入力待ちか、エンジンに考えさせる．
+// 置けなければパスし、相手も置けなければ終局．
+fn getNextState(
+    board: &board::Board,
+    turn: &board::Piece,
+    players: &mut [Option<Box<dyn Player>>; 2],
+    optClock: &Option<clock::Clock>,
+    status: &mut Status) -> GameState
+{
+    if board.searchPos(turn).len() > 0 {
+        let slot = getSlot(turn);
+        let bHuman = players[slot].as_ref().map_or(false, |p| p.isHuman());
+        if bHuman {
+            return GameState::AwaitingHuman;
+        }
+        if let Some(mut player) = players[slot].take() {
+            if let Some(clock) = optClock {
+                player.setTimeLeft(clock.getRemaining(turn), clock.increment);
             }
+
+            // 別スレッドで考えさせる
+            println!("Hmm ... ");
+            let (tx, rx) = channel();
+            let b = board.clone();
+            let t = *turn;
+            thread::spawn(move || {
+                let optMove = player.getMove(&b, &t);
+                let _ = tx.send((player, optMove));
+            });
+            return GameState::Thinking(rx);
         }
+        // 考えている途中でエンジンが止まり、プレイヤーが戻ってこなかった
+        println!("The engine stopped unexpectedly.");
+        status.message = "Engine stopped".to_string();
+        return GameState::GameOver;
+    }
+
+    if board.searchPos(&board::Piece::getOpponent(turn)).len() > 0 {
+        println!("No place for {}. Pass.", pieceMark(turn));
+        status.message = format!("{} passed", pieceName(turn));
+        return GameState::Passed(PASS_FRAMES);
     }
+
+    println!("*** Game Over ***");
+    println!("{}", board.getCount());
+    status.message = getResultText(board);
+    return GameState::GameOver;
 }
 
-fn main() {
-    let mut optBoardPath: Option<String> = None;
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() >= 2 {
-        optBoardPath = Some(args[1].to_string());
+// エンジンが考えていれば中断させ、プレイヤーが戻ってくるのを待つ
+fn stopThinking(state: &GameState, abort: &AtomicBool, turn: &board::Piece, players: &mut [Option<Box<dyn Player>>; 2]) {
+    if let GameState::Thinking(rx) = state {
+        abort.store(true, Ordering::Relaxed);
+        if let Ok((player, _)) = rx.recv() {
+            players[getSlot(turn)] = Some(player);
+        }
+        abort.store(false, Ordering::Relaxed);
     }
+}
 
-    // test00(optBoardPath);
-    // test01(optBoardPath);
-    // test02(optBoardPath);
-    // test03(optBoardPath);
-    // test04(optBoardPath);
+// アニメーションを始める（nFramesが0ならアニメーションしない）
+fn startAnimation(ret: &board::SearchResult2, nFrames: u32) -> Option<Animation> {
+    if nFrames == 0 {
+        return None;
+    }
+    return Some(Animation::new(ret, nFrames));
+}
+
+// キーから列(A-P → 1..16)を返す
+//
+// N、Pなど命令に使うキーは先に処理されるので、その列はカーソルで選ぶ
+fn getColumnFromKey(keycode: Keycode) -> Option<i32> {
+    return match keycode {
+        Keycode::A => Some(1),
+        Keycode::B => Some(2),
+        Keycode::C => Some(3),
+        Keycode::D => Some(4),
+        Keycode::E => Some(5),
+        Keycode::F => Some(6),
+        Keycode::G => Some(7),
+        Keycode::H => Some(8),
+        Keycode::I => Some(9),
+        Keycode::J => Some(10),
+        Keycode::K => Some(11),
+        Keycode::L => Some(12),
+        Keycode::M => Some(13),
+        Keycode::N => Some(14),
+        Keycode::O => Some(15),
+        Keycode::P => Some(16),
+        _ => None
+    };
+}
+
+// キーから行(1..9)を返す（10行目以降はカーソルで選ぶ）
+fn getRowFromKey(keycode: Keycode) -> Option<i32> {
+    return match keycode {
+        Keycode::Num1 | Keycode::Kp1 => Some(1),
+        Keycode::Num2 | Keycode::Kp2 => Some(2),
+        Keycode::Num3 | Keycode::Kp3 => Some(3),
+        Keycode::Num4 | Keycode::Kp4 => Some(4),
+        Keycode::Num5 | Keycode::Kp5 => Some(5),
+        Keycode::Num6 | Keycode::Kp6 => Some(6),
+        Keycode::Num7 | Keycode::Kp7 => Some(7),
+        Keycode::Num8 | Keycode::Kp8 => Some(8),
+        Keycode::Num9 | Keycode::Kp9 => Some(9),
+        _ => None
+    };
+}
 
-    game(optBoardPath);
-}
\ No newline at end of file
+// playersの中のpieceのプレイヤーの位置
+fn getSlot(piece: &board::Piece) -> usize {
+    return if *piece == board::Piece::White { 1 } else { 0 };
+}
+
+fn pieceMark(piece: &board::Piece) -> &str {
+    return match piece {
+        board::Piece::White => "●",
+        board::Piece::Black => "○",
+        _ => "・"
+    };
+}
+
+// コンソールで対局する
+//
+// optSavePathがあれば終局時に棋譜を保存する
+fn console(
+    optBoardPath: Option<String>,
+    mut black: Box<dyn Player>,
+    mut white: Box<dyn Player>,
+    optSavePath: Option<String>,
+    optClockSetting: Option<(Duration, Duration)>,
+    handicap: usize,
+    size: i32)
+{
+    let mut board = match loadBoard(&optBoardPath, size) {
+        Some(b) => b,
+        None => {
+            return;
+        }
+    };
+    level::applyHandicap(&mut board, &board::Piece::Black, handicap);
+
+    println!("{}", board);
+
+    let mut turn = board::Piece::Black;
+    let mut gameRecord = record::GameRecord::new(&board, &turn);
+    let mut optClock = optClockSetting.map(|(initial, increment)| clock::Clock::new(initial, increment));
+    let mut bTimeout = false;   // 手番の側が時間切れになった
+    loop {
+        if board.searchPos(&turn).len() == 0 {
+            if board.searchPos(&board::Piece::getOpponent(&turn)).len() == 0 {
+                break;
+            }
+            println!("No place for {}. Pass.", pieceMark(&turn));
+            gameRecord.push(&turn, None);
+            turn = board::Piece::getOpponent(&turn);
+            continue;
+        }
+
+        let player = if turn == board::Piece::Black { &mut black } else { &mut white };
+        if let Some(clock) = &mut optClock {
+            player.setTimeLeft(clock.getRemaining(&turn), clock.increment);
+            clock.start(&turn);
+        }
+        let optMove = player.getMove(&board, &turn);
+        if optMove.is_none() {
+            // 入力が閉じられた
+            println!("No input from {}.", player.getName());
+            break;
+        }
+        if let Some(report) = player.getLastReport() {
+            println!("{}", report);
+        }
+        if optClock.as_ref().map_or(false, |clock| clock.isFlagged(&turn)) {
+            bTimeout = true;
+            break;
+        }
+        if let Some(ret) = applyMove(&mut board, &turn, &player.getName(), optMove) {
+            if let Some(clock) = &mut optClock {
+                clock.punch();
+            }
+            gameRecord.push(&turn, Some(ret.pos));
+            turn = board::Piece::getOpponent(&turn);
+        }
+        if let Some(clock) = &optClock {
+            println!("clock: {}", getClockText(clock.getRemaining(&board::Piece::Black), clock.getRemaining(&board::Piece::White)));
+        }
+    }
+
+    println!("*** Game Over ***");
+    if bTimeout {
+        println!("{} loses on time", pieceName(&turn));
+    }
+    println!("{}", board.getCount());
+
+    if let Some(path) = optSavePath {
+        println!("{}", saveRecord(&gameRecord, &path));
+    }
+}
+
+// 棋譜の手順を表示する（currentは現在の局面の手数で、その手を[]で囲む）
+fn printMoveList(gameRecord: &record::GameRecord, current: usize) {
+    let mut line = String::new();
+    for (i, m) in gameRecord.moves.iter().enumerate() {
+        let desc = format!("{}.{}", i + 1, record::GameRecord::getMoveDesc(m));
+        if i + 1 == current {
+            line.push_str(&format!("[{}] ", desc));
+        } else {
+            line.push_str(&format!("{} ", desc));
+        }
+        if (i + 1) % 10 == 0 {
+            println!("{}", line);
+            line.clear();
+        }
+    }
+    if line.len() > 0 {
+        println!("{}", line);
+    }
+}
+
+// 棋譜をコンソールで再生する
+fn consoleReview(gameRecord: &record::GameRecord) {
+    let steps = match gameRecord.replay() {
+        Ok(s) => s,
+        Err(e) => {
+            println!("invalid record: {}", e);
+            return;
+        }
+    };
+
+    let last = steps.len() - 1;
+    let mut index = 0;
+    loop {
+        let step = &steps[index];
+        println!();
+        printMoveList(gameRecord, index);
+        println!("move {}/{}", index, last);
+        println!("{}", step.board);
+        println!("{}", step.board.getCount());
+        if index > 0 {
+            let m = &gameRecord.moves[index - 1];
+            if let Some(ret) = &step.optResult {
+                let flips = ret.capturedPieceLocs.iter().map(|p| board::Pos::toDesc(p.x, p.y)).collect::<Vec<String>>();
+                println!("{} {} flips {}", pieceMark(&m.piece), record::GameRecord::getMoveDesc(m), flips.join(" "));
+            } else {
+                println!("{} pass", pieceMark(&m.piece));
+            }
+        }
+
+        print!("(n)ext (p)rev (f)irst (l)ast (j N) jump (q)uit > ");
+        stdout().flush().unwrap();
+        let mut line = String::new();
+        if stdin().read_line(&mut line).is_err() || line.len() == 0 {
+            return;
+        }
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        match words.first().map(|w| w.to_lowercase()).as_deref() {
+            None | Some("n") => index = (index + 1).min(last),
+            Some("p") => index = index.saturating_sub(1),
+            Some("f") => index = 0,
+            Some("l") => index = last,
+            Some("j") => {
+                if let Some(n) = words.get(1).and_then(|w| w.parse::<usize>().ok()) {
+                    index = n.min(last);
+                }
+            },
+            Some("q") => {
+                return;
+            },
+            _ => {
+                println!("unknown command");
+            }
+        }
+    }
+}
+
+// 開始画面で選ぶ設定
+struct GameSettings {
+    playerPiece: board::Piece,  // 人間の駒
+    level: usize,               // コンピュータの強さ（level::LEVELSのインデックス）
+    handicap: usize,            // 人間の駒を置いておく隅の数
+    bLoadFile: bool,            // 読み込んだ盤から始める（falseなら初期状態）
+    firstPiece: board::Piece,   // 先手
+    size: i32                   // 盤の一辺のマス数
+}
+
+// 評価値を表示するときの読みの深さ
+const EVAL_DEPTH: i32 = 5;
+
+// 別スレッドでの評価値の計算（中断用のフラグと結果の受け取り口）
+type EvalJob = (Arc<AtomicBool>, Receiver<Vec<(board::Pos, i32)>>);
+
+fn pieceName(piece: &board::Piece) -> &str {
+    return match piece {
+        board::Piece::White => "White",
+        board::Piece::Black => "Black",
+        _ => "-"
+    };
+}
+
+// 開始画面の各行の文字列
+fn getStartScreenItems(settings: &GameSettings, bHasFile: bool) -> Vec<String> {
+    let position = if !bHasFile {
+        "Initial"
+    } else if settings.bLoadFile {
+        "File"
+    } else {
+        "Initial"
+    };
+    return vec!(
+        format!("You play : {}", pieceName(&settings.playerPiece)),
+        format!("Level    : {}", level::LEVELS[settings.level].name),
+        format!("Handicap : {}", settings.handicap),
+        format!("Position : {}", position),
+        format!("First    : {}", pieceName(&settings.firstPiece)),
+        format!("Size     : {}x{}", settings.size, settings.size),
+        "[ Start ]".to_string()
+    );
+}
+
+fn drawStartScreen<'a>(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    settings: &GameSettings,
+    bHasFile: bool) -> ()
+{
+    // 文字の大きさは盤の大きさによらないので既定の大きさで計算する
+    let layout = Layout::fromCanvas(canvas, board::DEFAULT_SIZE);
+    let ts = layout.ts;
+    let x = layout.lm + ts * 2;
+
+    canvas.set_draw_color(Color::RGB(0, 0, 0));
+    canvas.clear();
+
+    Util::textOut(canvas, &font, &texture_creator, "Othello", 255, 255, 255, x, layout.margin * 2, (ts * 5 / 8) as u32, ts as u32);
+
+    let items = getStartScreenItems(settings, bHasFile);
+    for i in 0..items.len() {
+        Util::textOut(canvas, &font, &texture_creator, &items[i], 255, 255, 255, x, layout.getStartRowY(i as i32), (ts * 5 / 16) as u32, (ts / 2) as u32);
+    }
+    let hintY = layout.getStartRowY(items.len() as i32 + 1);
+    Util::textOut(canvas, &font, &texture_creator, "click an item to change it", 160, 160, 160, x, hintY, (ts / 4) as u32, (ts * 3 / 8) as u32);
+
+    canvas.present();
+}
+
+// 開始画面を表示し、設定を選ばせる
+//
+// @return ウィンドウが閉じられた場合はNone
+fn startScreen<'a>(
+    canvas: &mut Canvas<Window>,
+    font: &Font,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    event_pump: &mut sdl2::EventPump,
+    bHasFile: bool,
+    size: i32) -> Option<GameSettings>
+{
+    let mut settings = GameSettings {
+        playerPiece: board::Piece::Black,
+        level: level::LEVELS.iter().position(|l| l.name == "expert").unwrap_or(0),
+        handicap: 0,
+        bLoadFile: bHasFile,
+        firstPiece: board::Piece::Black,
+        size
+    };
+
+    loop {
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape), ..
+                } => {
+                    return None;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::Return), ..
+                } => {
+                    return Some(settings);
+                },
+                Event::MouseButtonDown {
+                    y, ..
+                } => {
+                    // クリックされた行の設定を切り替える
+                    let layout = Layout::fromCanvas(canvas, board::DEFAULT_SIZE);
+                    let row = layout.getStartRowAt(y, 7);
+                    match row {
+                        Some(0) => {
+                            settings.playerPiece = board::Piece::getOpponent(&settings.playerPiece);
+                        },
+                        Some(1) => {
+                            settings.level = (settings.level + 1) % level::LEVELS.len();
+                        },
+                        Some(2) => {
+                            settings.handicap = (settings.handicap + 1) % 5;
+                        },
+                        Some(3) => {
+                            settings.bLoadFile = bHasFile && !settings.bLoadFile;
+                        },
+                        Some(4) => {
+                            settings.firstPiece = board::Piece::getOpponent(&settings.firstPiece);
+                        },
+                        Some(5) => {
+                            // 偶数の大きさを順に（最大の次は最小）
+                            settings.size += 2;
+                            if settings.size > board::MAX_SIZE {
+                                settings.size = board::MIN_SIZE;
+                            }
+                        },
+                        Some(6) => {
+                            return Some(settings);
+                        },
+                        _ => {}
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        drawStartScreen(canvas, font, texture_creator, &settings, bHasFile);
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+// GUIの設定
+struct GuiOptions {
+    animFrames: u32,                // 駒を置く・裏返すアニメーションのフレーム数（0ならアニメーションしない）
+    theme: Theme,
+    optSavePath: Option<String>,    // 終局時に棋譜を保存するファイル
+    optConnect: Option<String>,     // 対局サーバー（host:port）に接続して打つ
+    optClock: Option<(Duration, Duration)>, // 持ち時間と1手ごとの増加時間（Noneは無制限）
+    handicap: usize,                // 黒の駒を置いておく隅の数（開始画面では人間の側）
+    size: i32                       // 盤の一辺のマス数（開始画面で変えられる）
+}
+
+// 棋譜を再生する
+//
+// ←→で1手ずつ、Home/Endで最初/最後、PageUp/PageDownで10手ずつ移動する．
+// 数字を入力してEnterでその手数に、右側の手順をクリックでその手に移動する．
+fn review<'a>(
+    canvas: &mut Canvas<Window>,
+    event_pump: &mut sdl2::EventPump,
+    optTexture: Option<&Texture>,
+    font: &Font,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    options: &GuiOptions,
+    gameRecord: &record::GameRecord)
+{
+    let theme = &options.theme;
+    let steps = match gameRecord.replay() {
+        Ok(s) => s,
+        Err(e) => {
+            println!("invalid record: {}", e);
+            return;
+        }
+    };
+
+    let last = steps.len() - 1;
+    let mut index: usize = 0;
+    let mut typed = String::new();  // 入力中の手数
+    let mut optAnimation: Option<Animation> = None;
+    let nListLines = 14;            // 手順を表示する行数
+
+    loop {
+        let layout = Layout::fromCanvas(canvas, steps[index].board.getSize());
+        let lineH = layout.ts * 3 / 8;
+        let listTop = layout.getStatusLineY(4);
+        let firstListed = index.saturating_sub(nListLines / 2).min(gameRecord.moves.len().saturating_sub(nListLines));
+
+        let mut optNext: Option<usize> = None;
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape), ..
+                } => {
+                    return;
+                },
+                Event::KeyDown {
+                    keycode: Some(keycode), ..
+                } => {
+                    match keycode {
+                        Keycode::Right | Keycode::Space => optNext = Some((index + 1).min(last)),
+                        Keycode::Left | Keycode::Backspace => optNext = Some(index.saturating_sub(1)),
+                        Keycode::Home => optNext = Some(0),
+                        Keycode::End => optNext = Some(last),
+                        Keycode::PageDown => optNext = Some((index + 10).min(last)),
+                        Keycode::PageUp => optNext = Some(index.saturating_sub(10)),
+                        Keycode::Return | Keycode::KpEnter => {
+                            if let Ok(n) = typed.parse::<usize>() {
+                                optNext = Some(n.min(last));
+                            }
+                            typed.clear();
+                        },
+                        _ => {
+                            let name = keycode.name();
+                            if (name.len() == 1) && name.chars().all(|c| c.is_ascii_digit()) {
+                                typed.push_str(&name);
+                            }
+                        }
+                    }
+                },
+                Event::MouseButtonDown {
+                    x, y, ..
+                } => {
+                    // 手順の行をクリックしたらその手に移動する
+                    let px = ((x as f64) * layout.scale) as i32;
+                    let py = ((y as f64) * layout.scale) as i32;
+                    if (px >= layout.panelX) && (py >= listTop) {
+                        let row = ((py - listTop) / lineH.max(1)) as usize;
+                        if row < nListLines {
+                            optNext = Some((firstListed + row + 1).min(last));
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        if let Some(next) = optNext {
+            // 1手進めたときだけアニメーションする
+            optAnimation = None;
+            if (next == index + 1) && (options.animFrames > 0) {
+                if let Some(ret) = &steps[next].optResult {
+                    optAnimation = Some(Animation::new(ret, options.animFrames));
+                }
+            }
+            index = next;
+        }
+
+        let step = &steps[index];
+        let mut overlay = Overlay::new();
+        if let Some(ret) = &step.optResult {
+            overlay.lastMove = Some(ret.pos);
+            if optAnimation.is_none() {
+                overlay.flips = ret.capturedPieceLocs.clone();
+            }
+        }
+        drawBoard(canvas, optTexture, font, texture_creator, &layout, theme, &step.board, &overlay, optAnimation.as_ref());
+
+        // 石数と手順
+        let x = layout.panelX + layout.margin;
+        let wl = (layout.ts / 8) as u32;
+        let hl = (layout.ts * 5 / 24) as u32;
+        let tc = theme.textColor;
+        let mc = theme.messageColor;
+        let count = step.board.getCount();
+        let header = [
+            format!("Black : {}", count.nBlackPieces),
+            format!("White : {}", count.nWhitePieces),
+            format!("Move  : {}/{} {}", index, last, typed)
+        ];
+        for i in 0..header.len() {
+            Util::textOut(canvas, font, texture_creator, &header[i], tc.r, tc.g, tc.b, x, layout.getStatusLineY(i as i32), wl, hl);
+        }
+        for row in 0..nListLines {
+            let i = firstListed + row;
+            if i >= gameRecord.moves.len() {
+                break;
+            }
+            let m = &gameRecord.moves[i];
+            let text = format!("{:>2}. {} {}", i + 1, pieceName(&m.piece), record::GameRecord::getMoveDesc(m));
+            let c = if i + 1 == index { mc } else { tc };
+            Util::textOut(canvas, font, texture_creator, &text, c.r, c.g, c.b, x, listTop + (row as i32) * lineH, wl, hl);
+        }
+        canvas.present();
+
+        if let Some(anim) = optAnimation.as_mut() {
+            anim.frame += 1;
+            if anim.isFinished() {
+                optAnimation = None;
+            }
+        }
+        thread::sleep(Duration::from_millis(16));
+    }
+}
+
+// 対局サーバーに接続して打つ
+//
+// 盤はサーバーから送られたものを表示し、自分の手番ならクリックした場所を送る．
+// Rで投了する．
+fn remoteGame<'a>(
+    canvas: &mut Canvas<Window>,
+    event_pump: &mut sdl2::EventPump,
+    optTexture: Option<&Texture>,
+    font: &Font,
+    texture_creator: &'a TextureCreator<WindowContext>,
+    options: &GuiOptions,
+    addr: &str)
+{
+    let theme = &options.theme;
+    let mut conn = match net::Connection::connect(addr, "human") {
+        Ok(c) => c,
+        Err(e) => {
+            println!("failed to connect to {}: {}", addr, e);
+            return;
+        }
+    };
+    println!("connected to {}", addr);
+
+    let mut myPiece = board::Piece::Space;  // 自分の色（観戦者はSpace）
+    let mut board = board::Board::new();
+    let mut turn = board::Piece::Space;     // 手番（対局中でなければSpace）
+    let mut overlay = Overlay::new();
+    let mut status = Status::new();
+    let mut optAnimation: Option<Animation> = None;
+    let mut optClock: Option<(Duration, Duration, Instant)> = None;     // 黒と白の残り時間と受け取った時刻
+    let mut bConnected = true;
+    status.message = "Waiting for opponent".to_string();
+
+    loop {
+        let layout = Layout::fromCanvas(canvas, board.getSize());
+        let bMyTurn = (myPiece != board::Piece::Space) && (turn == myPiece);
+
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape), ..
+                } => {
+                    return;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::R), ..
+                } => {
+                    if turn != board::Piece::Space && myPiece != board::Piece::Space {
+                        let _ = conn.send(&net::ClientMessage::Resign);
+                    }
+                },
+                Event::MouseButtonDown {
+                    x, y, ..
+                } => {
+                    if let Some(anim) = optAnimation.as_mut() {
+                        anim.skip();
+                    } else if bMyTurn {
+                        if let Some(pos) = layout.getPosAt(x, y) {
+                            // サーバーでも確かめるが、置けない場所はここで知らせる
+                            if board.put(&turn, &pos).is_some() {
+                                let _ = conn.send(&net::ClientMessage::Move(Some(pos)));
+                            } else {
+                                status.message = format!("You cannot place on {}", board::Pos::toDesc(pos.x, pos.y));
+                            }
+                        }
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        // サーバーからのメッセージ
+        while bConnected {
+            match conn.tryRecv() {
+                Ok(net::ServerMessage::Welcome(piece)) => {
+                    myPiece = piece;
+                    println!("playing as {}", if piece == board::Piece::Space { "spectator" } else { pieceName(&piece) });
+                },
+                Ok(net::ServerMessage::Board(b, t)) => {
+                    board = b;
+                    turn = t;
+                },
+                Ok(net::ServerMessage::Clock(black, white)) => {
+                    optClock = Some((black, white, Instant::now()));
+                },
+                Ok(net::ServerMessage::Move(piece, Some(pos))) => {
+                    if let Some(ret) = board.put(&piece, &pos) {
+                        board = ret.board.clone();
+                        optAnimation = startAnimation(&ret, options.animFrames);
+                    }
+                    overlay.lastMove = Some(pos);
+                    status.lastMove = format!("{} {}", pieceName(&piece), board::Pos::toDesc(pos.x, pos.y));
+                    status.message.clear();
+                },
+                Ok(net::ServerMessage::Move(piece, None)) => {
+                    status.message = format!("{} passed", pieceName(&piece));
+                },
+                Ok(net::ServerMessage::Error(text)) => {
+                    status.message = text;
+                },
+                Ok(net::ServerMessage::GameOver(count, winner, reason)) => {
+                    status.message = if winner == board::Piece::Space {
+                        format!("Draw {}-{}", count.nBlackPieces, count.nWhitePieces)
+                    } else {
+                        format!("{} wins ({})", pieceName(&winner), reason)
+                    };
+                    println!("{}", status.message);
+                },
+                Err(TryRecvError::Empty) => {
+                    break;
+                },
+                Err(TryRecvError::Disconnected) => {
+                    bConnected = false;
+                    status.message = "Disconnected".to_string();
+                    println!("disconnected from {}", addr);
+                }
+            }
+        }
+
+        overlay.hints.clear();
+        if bMyTurn && optAnimation.is_none() {
+            for res in &board.searchPos(&turn) {
+                overlay.hints.push(res.pos);
+            }
+        }
+
+        let turnText = if !bConnected {
+            "-".to_string()
+        } else if turn == board::Piece::Space {
+            "Waiting".to_string()
+        } else if myPiece == board::Piece::Space {
+            pieceName(&turn).to_string()
+        } else if turn == myPiece {
+            format!("{} (you)", pieceName(&turn))
+        } else {
+            format!("{} (opponent)", pieceName(&turn))
+        };
+        if let Some((black, white, received)) = optClock {
+            // 手番の側の時計は受け取ってからの時間を引いて表示する
+            let elapsed = received.elapsed();
+            let b = if turn == board::Piece::Black { black.checked_sub(elapsed).unwrap_or_default() } else { black };
+            let w = if turn == board::Piece::White { white.checked_sub(elapsed).unwrap_or_default() } else { white };
+            status.clock = getClockText(b, w);
+        }
+        drawBoard(canvas, optTexture, font, texture_creator, &layout, theme, &board, &overlay, optAnimation.as_ref());
+        drawStatus(canvas, font, texture_creator, &layout, theme, &board, &turnText, &status);
+        canvas.present();
+
+        if let Some(anim) = optAnimation.as_mut() {
+            anim.frame += 1;
+            if anim.isFinished() {
+                optAnimation = None;
+            }
+        }
+        thread::sleep(Duration::from_millis(16));
+    }
+}
+
+// 棋譜を保存するファイル（--saveがない場合）
+const DEFAULT_RECORD_PATH: &str = "game.txt";
+
+// 棋譜を保存し、結果の文字列を返す
+fn saveRecord(gameRecord: &record::GameRecord, path: &str) -> String {
+    return match gameRecord.save(path) {
+        Ok(_) => format!("Saved to {}", path),
+        Err(e) => format!("Save failed: {}", e)
+    };
+}
+
+// SDLのウィンドウで対局する
+//
+// optPlayersがNoneの場合は開始画面で人間の駒や強さを選ぶ．
+// optReviewがある場合は対局せず、その棋譜を再生する．
+fn game(
+    optBoardPath: Option<String>,
+    optPlayers: Option<(Box<dyn Player>, Box<dyn Player>)>,
+    optReview: Option<record::GameRecord>,
+    options: GuiOptions)
+{
+    let theme = &options.theme;
+    let animFrames = options.animFrames;
+    let VERSION = 0.3;
+    let title = format!("*** Othello (ver {}) ***", VERSION);
+    
+    let sdl2_context = sdl2::init().unwrap();
+    let video_subsystem = sdl2_context.video().unwrap();
+
+    let width: u32 = 1216;  // 右側に状態表示の欄を設ける
+    let height: u32 = 832;
+    let mut window = video_subsystem
+        .window(&title, width, height)
+        .position_centered()
+        .resizable()
+        .allow_highdpi()
+        .build()
+        .unwrap();
+    window.set_minimum_size(480, 320).expect("failed to set minimum window size");
+
+    let mut canvas = window.into_canvas().build().unwrap();
+
+    let texture_creator: TextureCreator<_> = canvas.texture_creator();
+    // 画像がなければ駒を図形で描く
+    let optImageTexture = findAsset(&theme.sprites).and_then(|path| texture_creator.load_texture(path).ok());
+    if optImageTexture.is_none() {
+        println!("failed to load {}. drawing discs without images.", theme.sprites);
+    }
+
+    // init font stuff
+    let ttf_context = sdl2::ttf::init().expect("failed to init SDL TTF");
+    let font = match findAsset(&theme.font).and_then(|path| ttf_context.load_font(path, 128).ok()) {
+        Some(f) => f,
+        None => {
+            println!("failed to load font {}", theme.font);
+            return;
+        }
+    };
+    
+    // clear canvas
+    canvas.set_draw_color(theme.background);
+    canvas.clear();
+    canvas.present();
+    
+    println!("***************************");
+    println!("  Othello Game (ver {})", VERSION);
+    println!("***************************");
+
+    let mut event_pump = sdl2_context.event_pump().unwrap();
+
+    if let Some(gameRecord) = optReview {
+        review(&mut canvas, &mut event_pump, optImageTexture.as_ref(), &font, &texture_creator, &options, &gameRecord);
+        return;
+    }
+
+    if let Some(addr) = &options.optConnect {
+        remoteGame(&mut canvas, &mut event_pump, optImageTexture.as_ref(), &font, &texture_creator, &options, addr);
+        return;
+    }
+
+    let mut turn = board::Piece::Black;
+    let mut optLoadPath = optBoardPath.clone();
+    let mut handicap = (board::Piece::Black, options.handicap);   // 隅を置いておく側とその数
+    let mut size = options.size;
+    let (black, white) = match optPlayers {
+        Some(players) => players,
+        None => {
+            let optSettings = startScreen(&mut canvas, &font, &texture_creator, &mut event_pump, optBoardPath.is_some(), size);
+            let settings = match optSettings {
+                Some(s) => s,
+                None => {
+                    return;
+                }
+            };
+            if !settings.bLoadFile {
+                optLoadPath = None;
+            }
+            turn = settings.firstPiece;
+            handicap = (settings.playerPiece, settings.handicap);
+            size = settings.size;
+
+            let human: Box<dyn Player> = Box::new(MousePlayer { input: None });
+            let computer: Box<dyn Player> = level::LEVELS[settings.level].createPlayer();
+            if settings.playerPiece == board::Piece::Black {
+                (human, computer)
+            } else {
+                (computer, human)
+            }
+        }
+    };
+
+    let mut board = match loadBoard(&optLoadPath, size) {
+        Some(b) => b,
+        None => {
+            return;
+        }
+    };
+    level::applyHandicap(&mut board, &handicap.0, handicap.1);
+
+    println!("{}", board);
+
+    // 新しい対局はこの局面から始める
+    let startBoard = board.clone();
+    let startTurn = turn;
+
+    // エンジンは別スレッドで考えさせる．考えている間、プレイヤーはスレッドに渡している
+    let mut players: [Option<Box<dyn Player>>; 2] = [Some(black), Some(white)];
+    let abort = Arc::new(AtomicBool::new(false));
+    for player in players.iter_mut().flatten() {
+        player.setAbortFlag(abort.clone());
+    }
+    // 待ったのための履歴（盤、手番、最後に打たれた場所、その時点の棋譜の手数）
+    let mut history: Vec<(board::Board, board::Piece, Option<board::Pos>, usize)> = vec!();
+    let mut gameRecord = record::GameRecord::new(&startBoard, &startTurn);
+    let mut bAutoSaved = false;     // 終局時に棋譜を保存した
+    let startTime = Instant::now();
+
+    let mut overlay = Overlay::new();
+    let mut bShowHints = true;     // 置ける場所を表示する
+    let mut bShowEvals = false;    // 評価値を表示する
+    let mut optEvalKey: Option<(board::Board, board::Piece)> = None;   // 評価値を計算した局面
+    let mut optEvalJob: Option<EvalJob> = None;    // 別スレッドで計算中の評価値
+    let mut status = Status::new();
+    let n = board.getSize();
+    let mut cursor = board::Pos { x: n / 2, y: n / 2 };    // キーボードで動かすカーソル（最初は中央）
+    let mut bShowCursor = false;                    // キーボードを使ったらカーソルを表示する
+    let mut optTypedColumn: Option<i32> = None;     // 入力中の座標の列（"f5"の"f"）
+
+    println!("keys: arrows/enter = move cursor/play, a-{} 1-{} = play (ex. {}), P = skip pass, U = undo, N = new game, S = save",
+        board::Pos::getColumnName(n).to_lowercase(), n.min(9), board::Pos::toDesc(n / 2, n / 2).to_lowercase());
+    println!("      F2 = toggle hints, F3 = toggle evaluations, Esc = quit");
+    // N、Pはコマンドのキーなので列として打てない．行は数字1つなので9行目まで
+    let mut cursorOnly = (1..=n).map(board::Pos::getColumnName).filter(|c| c == "N" || c == "P").map(|c| format!("column {}", c)).collect::<Vec<String>>();
+    if n > 9 {
+        cursorOnly.push(format!("rows 10-{}", n));
+    }
+    if cursorOnly.len() > 0 {
+        println!("      {} can only be played with the cursor", cursorOnly.join(", "));
+    }
+
+    let mut optClock = options.optClock.map(|(initial, increment)| clock::Clock::new(initial, increment));
+    let mut state = getNextState(&board, &turn, &mut players, &optClock, &mut status);
+
+    'running: loop {
+        // ウィンドウの大きさが変わっても追従するよう毎回計算する
+        let layout = Layout::fromCanvas(&canvas, board.getSize());
+
+        let mut bUndo = false;
+        let mut bNewGame = false;
+        let mut bSave = false;
+        for event in event_pump.poll_iter() {
+            match event {
+                Event::Quit { .. }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Escape), ..
+                } => {
+                    // 考え中なら中断させる
+                    abort.store(true, Ordering::Relaxed);
+                    break 'running;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::U), ..
+                }
+                | Event::KeyDown {
+                    keycode: Some(Keycode::Backspace), ..
+                } => {
+                    bUndo = true;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::N), ..
+                } => {
+                    bNewGame = true;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::F2), ..
+                } => {
+                    bShowHints = !bShowHints;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::F3), ..
+                } => {
+                    bShowEvals = !bShowEvals;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::S), ..
+                } => {
+                    bSave = true;
+                },
+                Event::KeyDown {
+                    keycode: Some(Keycode::P), ..
+                } => {
+                    // パスの知らせを待たずに相手の手番にする
+                    if let GameState::Passed(n) = &mut state {
+                        *n = 0;
+                    }
+                },
+                Event::KeyDown {
+                    keycode: Some(keycode), ..
+                } => {
+                    // カーソル移動、座標の入力
+                    let mut optInput = None;
+                    match keycode {
+                        Keycode::Left => cursor.x = (cursor.x - 1).max(1),
+                        Keycode::Right => cursor.x = (cursor.x + 1).min(board.getSize()),
+                        Keycode::Up => cursor.y = (cursor.y - 1).max(1),
+                        Keycode::Down => cursor.y = (cursor.y + 1).min(board.getSize()),
+                        Keycode::Return | Keycode::KpEnter | Keycode::Space => optInput = Some(cursor),
+                        _ => {
+                            if let Some(x) = getColumnFromKey(keycode).filter(|&x| x <= board.getSize()) {
+                                optTypedColumn = Some(x);
+                            } else if let (Some(x), Some(y)) = (optTypedColumn, getRowFromKey(keycode)) {
+                                cursor = board::Pos { x, y };
+                                optInput = Some(cursor);
+                                optTypedColumn = None;
+                            }
+                        }
+                    }
+                    bShowCursor = true;
+
+                    if let Some(pos) = optInput {
+                        match &mut state {
+                            GameState::Animating(anim) => {
+                                anim.skip();
+                            },
+                            GameState::AwaitingHuman => {
+                                if let Some(player) = players[getSlot(&turn)].as_mut() {
+                                    player.setInput(&pos);
+                                }
+                            },
+                            _ => {}
+                        }
+                    }
+                },
+                Event::MouseButtonDown {
+                    x, y, ..
+                } => {
+                    // println!("(x,y)=({},{})", x, y);
+                    match &mut state {
+                        GameState::Animating(anim) => {
+                            // アニメーション中のクリックはアニメーションを飛ばす
+                            anim.skip();
+                        },
+                        GameState::AwaitingHuman => {
+                            if let Some(pos) = layout.getPosAt(x, y) {
+                                // 手番のプレイヤーに渡す
+                                if let Some(player) = players[getSlot(&turn)].as_mut() {
+                                    player.setInput(&pos);
+                                }
+                            }
+                        },
+                        _ => {}
+                    }
+                },
+                _ => {}
+            }
+        }
+
+        if bUndo || bNewGame {
+            stopThinking(&state, &abort, &turn, &mut players);
+
+            if bNewGame {
+                board = startBoard.clone();
+                turn = startTurn;
+                history.clear();
+                gameRecord = record::GameRecord::new(&startBoard, &startTurn);
+                overlay.lastMove = None;
+                for player in players.iter_mut().flatten() {
+                    player.reset();
+                }
+                if let Some(clock) = &mut optClock {
+                    clock.reset();
+                }
+                status = Status::new();
+                status.message = "New game".to_string();
+                println!("*** New Game ***");
+            } else {
+                // 人間の手番まで戻す
+                while let Some((prevBoard, prevTurn, prevLastMove, nRecordMoves)) = history.pop() {
+                    board = prevBoard;
+                    turn = prevTurn;
+                    overlay.lastMove = prevLastMove;
+                    gameRecord.moves.truncate(nRecordMoves);
+                    let bHuman = players[getSlot(&turn)].as_ref().map_or(false, |p| p.isHuman());
+                    if bHuman || history.len() == 0 {
+                        break;
+                    }
+                }
+                status.message = "Undo".to_string();
+                println!("Undo.");
+            }
+            println!("{}", board);
+            bAutoSaved = false;
+            state = getNextState(&board, &turn, &mut players, &optClock, &mut status);
+        }
+
+        if bSave {
+            let path = options.optSavePath.clone().unwrap_or(DEFAULT_RECORD_PATH.to_string());
+            status.message = saveRecord(&gameRecord, &path);
+        }
+
+        // 時間切れ
+        let bFlagged = match (&state, &optClock) {
+            (GameState::AwaitingHuman, Some(clock)) | (GameState::Thinking(_), Some(clock)) => clock.isFlagged(&turn),
+            _ => false
+        };
+        if bFlagged {
+            stopThinking(&state, &abort, &turn, &mut players);
+            status.message = format!("{} loses on time", pieceName(&turn));
+            println!("{}", status.message);
+            state = GameState::GameOver;
+        }
+
+        // 状態ごとの処理
+        state = match state {
+            GameState::AwaitingHuman => match players[getSlot(&turn)].as_mut() {
+                None => {
+                    status.message = "Engine stopped".to_string();
+                    GameState::GameOver
+                },
+                Some(player) => {
+                    let optMove = player.getMove(&board, &turn);
+                    if optMove.is_some() {
+                        history.push((board.clone(), turn, overlay.lastMove, gameRecord.moves.len()));
+                    }
+                    match applyMove(&mut board, &turn, &player.getName(), optMove) {
+                        Some(ret) => {
+                            if let Some(clock) = &mut optClock {
+                                clock.punch();
+                            }
+                            gameRecord.push(&turn, Some(ret.pos));
+                            status.lastMove = format!("{} {}", pieceName(&turn), board::Pos::toDesc(ret.pos.x, ret.pos.y));
+                            status.message.clear();
+                            overlay.lastMove = Some(ret.pos);
+                            turn = board::Piece::getOpponent(&turn);
+                            match startAnimation(&ret, animFrames) {
+                                Some(anim) => GameState::Animating(anim),
+                                None => getNextState(&board, &turn, &mut players, &optClock, &mut status)
+                            }
+                        },
+                        None => {
+                            // 入力待ち、または置けない場所だった
+                            if optMove.is_some() {
+                                history.pop();
+                            }
+                            GameState::AwaitingHuman
+                        }
+                    }
+                }
+            },
+            GameState::Thinking(rx) => {
+                match rx.try_recv() {
+                    Ok((player, optMove)) => {
+                        let name = player.getName();
+                        status.eval = player.getEvaluation().unwrap_or("-".to_string());
+                        players[getSlot(&turn)] = Some(player);
+                        history.push((board.clone(), turn, overlay.lastMove, gameRecord.moves.len()));
+                        match applyMove(&mut board, &turn, &name, optMove) {
+                            Some(ret) => {
+                                if let Some(clock) = &mut optClock {
+                                    clock.punch();
+                                }
+                                gameRecord.push(&turn, Some(ret.pos));
+                                status.lastMove = format!("{} {}", pieceName(&turn), board::Pos::toDesc(ret.pos.x, ret.pos.y));
+                                overlay.lastMove = Some(ret.pos);
+                                turn = board::Piece::getOpponent(&turn);
+                                match startAnimation(&ret, animFrames) {
+                                    Some(anim) => GameState::Animating(anim),
+                                    None => getNextState(&board, &turn, &mut players, &optClock, &mut status)
+                                }
+                            },
+                            None => {
+                                history.pop();
+                                println!("{} returned an illegal move.", name);
+                                status.message = "Illegal move from engine".to_string();
+                                GameState::GameOver
+                            }
+                        }
+                    },
+                    Err(TryRecvError::Empty) => GameState::Thinking(rx),
+                    Err(TryRecvError::Disconnected) => {
+                        println!("The engine stopped unexpectedly.");
+                        status.message = "Engine stopped".to_string();
+                        GameState::GameOver
+                    }
+                }
+            },
+            GameState::Animating(mut anim) => {
+                // アニメーションが終わるまで次の手に進まない
+                anim.frame += 1;
+                if anim.isFinished() {
+                    getNextState(&board, &turn, &mut players, &optClock, &mut status)
+                } else {
+                    GameState::Animating(anim)
+                }
+            },
+            GameState::Passed(n) => {
+                // パスの知らせを少しの間表示してから相手の手番にする
+                if n == 0 {
+                    gameRecord.push(&turn, None);
+                    turn = board::Piece::getOpponent(&turn);
+                    getNextState(&board, &turn, &mut players, &optClock, &mut status)
+                } else {
+                    GameState::Passed(n - 1)
+                }
+            },
+            GameState::GameOver => GameState::GameOver
+        };
+
+        if let GameState::GameOver = state {
+            if !bAutoSaved {
+                if let Some(path) = &options.optSavePath {
+                    println!("{}", saveRecord(&gameRecord, path));
+                }
+                bAutoSaved = true;
+            }
+        }
+
+        // 手番の側が考えている間だけ時計を動かす
+        if let Some(clock) = &mut optClock {
+            match state {
+                GameState::AwaitingHuman | GameState::Thinking(_) => {
+                    if clock.getRunning() != Some(turn) {
+                        clock.start(&turn);
+                    }
+                },
+                _ => clock.pause()
+            }
+            status.clock = getClockText(clock.getRemaining(&board::Piece::Black), clock.getRemaining(&board::Piece::White));
+        }
+
+        // 人間の手番なら置ける場所と評価値を表示する
+        let bHumanTurn = match state {
+            GameState::AwaitingHuman => true,
+            _ => false
+        };
+        overlay.optCursor = if bShowCursor && bHumanTurn { Some(cursor) } else { None };
+        overlay.hints.clear();
+        if bHumanTurn && bShowHints {
+            for res in &board.searchPos(&turn) {
+                overlay.hints.push(res.pos);
+            }
+        }
+        if bHumanTurn && bShowEvals {
+            let key = (board.clone(), turn);
+            if optEvalKey.as_ref() != Some(&key) {
+                // 大きい盤では時間がかかるので別スレッドで計算し、終わったら表示する
+                if let Some((evalAbort, _)) = optEvalJob.take() {
+                    evalAbort.store(true, Ordering::Relaxed);
+                }
+                overlay.evals.clear();
+                let evalAbort = Arc::new(AtomicBool::new(false));
+                let (tx, rx) = channel();
+                let (b, t, a) = (board.clone(), turn, evalAbort.clone());
+                thread::spawn(move || {
+                    let _ = tx.send(b.getMoveValuesWithAbort(&t, EVAL_DEPTH, &a));
+                });
+                optEvalJob = Some((evalAbort, rx));
+                optEvalKey = Some(key);
+            }
+            let optValues = optEvalJob.as_ref().and_then(|(_, rx)| rx.try_recv().ok());
+            if let Some(values) = optValues {
+                overlay.evals = values;
+                optEvalJob = None;
+            }
+        } else {
+            if let Some((evalAbort, _)) = optEvalJob.take() {
+                evalAbort.store(true, Ordering::Relaxed);
+            }
+            overlay.evals.clear();
+            optEvalKey = None;
+        }
+
+        let turnText = match state {
+            GameState::GameOver => "Game Over".to_string(),
+            GameState::Passed(_) => format!("{} passes", pieceName(&turn)),
+            GameState::Thinking(_) => {
+                let nDots = (startTime.elapsed().as_millis() / 300 % 4) as usize;
+                format!("{} thinking{}", pieceName(&turn), ".".repeat(nDots))
+            },
+            _ => {
+                let name = players[getSlot(&turn)].as_ref().map_or(String::new(), |p| p.getName());
+                format!("{} ({})", pieceName(&turn), name)
+            }
+        };
+        let optAnim = match &state {
+            GameState::Animating(anim) => Some(anim),
+            _ => None
+        };
+        drawBoard(&mut canvas, optImageTexture.as_ref(), &font, &texture_creator, &layout, theme, &board, &overlay, optAnim);
+        drawStatus(&mut canvas, &font, &texture_creator, &layout, theme, &board, &turnText, &status);
+        if let GameState::GameOver = state {
+            let x = layout.panelX + layout.margin;
+            Util::textOut(&mut canvas, &font, &texture_creator, "N : new game", 160, 160, 160, x, layout.getStatusLineY(8), (layout.ts / 8) as u32, (layout.ts * 5 / 24) as u32);
+        }
+        canvas.present();
+
+        thread::sleep(Duration::from_millis(16));
+    }
+}
+
+// 終局した棋譜をエンジンで解析する
+//
+// othello analyze GAME [--depth N] [--threshold N] [--out FILE]
+fn analyze(args: &[String]) {
+    let mut optGamePath: Option<String> = None;
+    let mut depth = 5;
+    let mut threshold = 50;
+    let mut optOutPath: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--depth" if i + 1 < args.len() => {
+                i += 1;
+                depth = args[i].parse::<i32>().unwrap_or(depth);
+            },
+            "--threshold" if i + 1 < args.len() => {
+                i += 1;
+                threshold = args[i].parse::<i32>().unwrap_or(threshold);
+            },
+            "--out" if i + 1 < args.len() => {
+                i += 1;
+                optOutPath = Some(args[i].to_string());
+            },
+            _ => {
+                optGamePath = Some(args[i].to_string());
+            }
+        }
+        i += 1;
+    }
+
+    let gamePath = match optGamePath {
+        Some(p) => p,
+        None => {
+            println!("usage: othello analyze GAME [--depth N] [--threshold N] [--out FILE]");
+            return;
+        }
+    };
+    let gameRecord = match record::GameRecord::load(&gamePath) {
+        Ok(r) => r,
+        Err(e) => {
+            println!("failed to load {}", e);
+            return;
+        }
+    };
+
+    let analyses = match analysis::analyzeGame(&gameRecord, depth, threshold) {
+        Ok(a) => a,
+        Err(e) => {
+            println!("invalid record: {}", e);
+            return;
+        }
+    };
+    print!("{}", analysis::formatAnalysis(&analyses));
+
+    if let Some(outPath) = optOutPath {
+        match std::fs::write(&outPath, analysis::formatAnnotated(&gameRecord, &analyses)) {
+            Ok(_) => println!("Saved to {}", outPath),
+            Err(e) => println!("failed to write {}: {}", outPath, e)
+        }
+    }
+}
+
+// "--size"で指定された盤の大きさを読む．使えない大きさなら知らせてNone
+fn parseSize(s: &str) -> Option<i32> {
+    match s.parse::<i32>() {
+        Ok(size) if board::isValidSize(size) => {
+            return Some(size);
+        },
+        _ => {
+            println!("invalid board size '{}' (even number from {} to {})", s, board::MIN_SIZE, board::MAX_SIZE);
+            return None;
+        }
+    }
+}
+
+// 2つのエンジンを対戦させ、勝敗とレーティング差を表示する
+//
+// othello match PLAYER_A PLAYER_B [--games N] [--size N]
+fn runMatch(args: &[String]) {
+    let mut specs = vec!();
+    let mut nGames = 16;
+    let mut size = board::DEFAULT_SIZE;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--games" if i + 1 < args.len() => {
+                i += 1;
+                nGames = args[i].parse::<usize>().unwrap_or(nGames);
+            },
+            "--size" if i + 1 < args.len() => {
+                i += 1;
+                size = match parseSize(&args[i]) {
+                    Some(size) => size,
+                    None => {
+                        return;
+                    }
+                };
+            },
+            _ => {
+                specs.push(args[i].to_string());
+            }
+        }
+        i += 1;
+    }
+
+    if specs.len() != 2 {
+        println!("usage: othello match PLAYER_A PLAYER_B [--games N] [--size N]");
+        return;
+    }
+    let mut a = match arena::createPlayer(&specs[0]) {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    let mut b = match arena::createPlayer(&specs[1]) {
+        Ok(p) => p,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    let result = arena::runMatch(&mut *a, &mut *b, size, nGames, &mut |n, outcome, aIsBlack| {
+        let (blackName, whiteName) = if aIsBlack { (&specs[0], &specs[1]) } else { (&specs[1], &specs[0]) };
+        println!(
+            "game {}: {} (Black) {}-{} {} (White)",
+            n, blackName, outcome.count.nBlackPieces, outcome.count.nWhitePieces, whiteName
+        );
+    });
+
+    match result {
+        Ok(r) => {
+            let (elo, error) = r.getElo();
+            println!("{} vs {}", specs[0], specs[1]);
+            println!("  games  : {}", r.getGames());
+            println!("  W/D/L  : {}/{}/{}", r.wins, r.draws, r.losses);
+            println!("  score  : {:.1}%", r.getScore() * 100.0);
+            println!("  margin : {:+.2} discs/game", r.getAverageMargin());
+            println!("  Elo    : {:+.0} +/- {:.0}", elo, error);
+        },
+        Err(e) => {
+            println!("match aborted: {}", e);
+        }
+    }
+}
+
+// 複数のエンジンで総当たり戦を行い、成績表とレーティングを表示する
+//
+// othello tournament PLAYER... [--games N] [--threads N] [--log DIR] [--size N]
+//
+// --gamesは1組あたりの局数．--logを指定すると1局ごとに棋譜をDIRに保存する．
+fn runTournament(args: &[String]) {
+    let mut specs = vec!();
+    let mut gamesPerPair = 4;
+    let mut size = board::DEFAULT_SIZE;
+    let mut nThreads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    let mut optLogDir: Option<String> = None;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--games" if i + 1 < args.len() => {
+                i += 1;
+                gamesPerPair = args[i].parse::<usize>().unwrap_or(gamesPerPair);
+            },
+            "--threads" if i + 1 < args.len() => {
+                i += 1;
+                nThreads = args[i].parse::<usize>().unwrap_or(nThreads);
+            },
+            "--log" if i + 1 < args.len() => {
+                i += 1;
+                optLogDir = Some(args[i].to_string());
+            },
+            "--size" if i + 1 < args.len() => {
+                i += 1;
+                size = match parseSize(&args[i]) {
+                    Some(size) => size,
+                    None => {
+                        return;
+                    }
+                };
+            },
+            _ => {
+                specs.push(args[i].to_string());
+            }
+        }
+        i += 1;
+    }
+
+    if specs.len() < 2 {
+        println!("usage: othello tournament PLAYER... [--games N] [--threads N] [--log DIR] [--size N]");
+        return;
+    }
+    if let Some(dir) = &optLogDir {
+        if let Err(e) = std::fs::create_dir_all(dir) {
+            println!("failed to create {}: {}", dir, e);
+            return;
+        }
+    }
+
+    let result = arena::runTournament(&specs, size, gamesPerPair, nThreads, &mut |n, pairing, outcome| {
+        let blackName = &specs[pairing.black];
+        let whiteName = &specs[pairing.white];
+        println!(
+            "game {}: {} (Black) {}-{} {} (White)",
+            n, blackName, outcome.count.nBlackPieces, outcome.count.nWhitePieces, whiteName
+        );
+        if let Some(dir) = &optLogDir {
+            let path = std::path::Path::new(dir).join(format!("game{:04}.txt", n));
+            let text = arena::formatGameLog("tournament", n, blackName, whiteName, outcome);
+            if let Err(e) = std::fs::write(&path, text) {
+                println!("failed to write {}: {}", path.display(), e);
+            }
+        }
+    });
+
+    match result {
+        Ok(r) => {
+            println!();
+            print!("{}", r.format());
+        },
+        Err(e) => {
+            println!("tournament aborted: {}", e);
+        }
+    }
+}
+
+// NBoardプロトコルのエンジンとして標準入出力で動く
+//
+// othello nboard [--depth N]
+fn runNBoard(args: &[String]) {
+    let mut depth = 9;
+    if args.len() >= 2 && args[0] == "--depth" {
+        depth = args[1].parse::<i32>().unwrap_or(depth);
+    }
+
+    let stdin = stdin();
+    let stdout = stdout();
+    if let Err(e) = nboard::run(&mut stdin.lock(), &mut stdout.lock(), depth) {
+        eprintln!("nboard: {}", e);
+    }
+}
+
+// GTPのエンジンとして標準入出力で動く
+//
+// othello gtp [--depth N]
+fn runGtp(args: &[String]) {
+    let mut depth = 9;
+    if args.len() >= 2 && args[0] == "--depth" {
+        depth = args[1].parse::<i32>().unwrap_or(depth);
+    }
+
+    let stdin = stdin();
+    let stdout = stdout();
+    if let Err(e) = gtp::run(&mut stdin.lock(), &mut stdout.lock(), depth) {
+        eprintln!("gtp: {}", e);
+    }
+}
+
+// 対局サーバーを立てる
+//
+// othello serve [--bind ADDR] [--time SECONDS] [--increment SECONDS] [--games N] [--size N]
+fn runServer(args: &[String]) {
+    let mut addr = "127.0.0.1:7878".to_string();
+    let mut optTime: Option<u64> = None;
+    let mut increment = 0;
+    let mut nGames = 0;
+    let mut size = board::DEFAULT_SIZE;
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--bind" if i + 1 < args.len() => {
+                i += 1;
+                addr = args[i].to_string();
+            },
+            "--time" if i + 1 < args.len() => {
+                i += 1;
+                optTime = args[i].parse::<u64>().ok();
+            },
+            "--increment" if i + 1 < args.len() => {
+                i += 1;
+                increment = args[i].parse::<u64>().unwrap_or(increment);
+            },
+            "--games" if i + 1 < args.len() => {
+                i += 1;
+                nGames = args[i].parse::<u32>().unwrap_or(nGames);
+            },
+            "--size" if i + 1 < args.len() => {
+                i += 1;
+                size = match parseSize(&args[i]) {
+                    Some(size) => size,
+                    None => {
+                        return;
+                    }
+                };
+            },
+            _ => {
+                println!("usage: othello serve [--bind ADDR] [--time SECONDS] [--increment SECONDS] [--games N] [--size N]");
+                return;
+            }
+        }
+        i += 1;
+    }
+
+    let listener = match std::net::TcpListener::bind(&addr) {
+        Ok(l) => l,
+        Err(e) => {
+            println!("failed to listen on {}: {}", addr, e);
+            return;
+        }
+    };
+    println!("listening on {}", addr);
+
+    let options = net::ServerOptions {
+        optClock: optTime.map(|t| (Duration::from_secs(t), Duration::from_secs(increment))),
+        nGames,
+        size
+    };
+    if let Err(e) = net::serve(listener, &options, &mut |text| println!("{}", text)) {
+        println!("server error: {}", e);
+    }
+}
+
+// サーバーに接続し、コンソールの人間かエンジンとして打つ
+//
+// othello connect HOST:PORT [--player PLAYER]
+fn runClient(args: &[String]) {
+    let mut optAddr: Option<String> = None;
+    let mut playerName = "human".to_string();
+
+    let mut i = 0;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--player" if i + 1 < args.len() => {
+                i += 1;
+                playerName = args[i].to_string();
+            },
+            _ => {
+                optAddr = Some(args[i].to_string());
+            }
+        }
+        i += 1;
+    }
+
+    let addr = match optAddr {
+        Some(a) => a,
+        None => {
+            println!("usage: othello connect HOST:PORT [--player PLAYER]");
+            return;
+        }
+    };
+    let mut player = match createPlayer(&playerName, true) {
+        Some(p) => p,
+        None => {
+            return;
+        }
+    };
+    let bHuman = player.isHuman();
+    let result = net::playRemote(&addr, &mut *player, &mut |line| {
+        match net::ServerMessage::parse(line) {
+            Some(net::ServerMessage::Board(board, _)) if bHuman => println!("{}", board),
+            _ => println!("{}", line)
+        }
+    });
+    if let Err(e) = result {
+        println!("connection error: {}", e);
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().collect();
+
+    // サブコマンド
+    if args.len() >= 2 {
+        match args[1].as_str() {
+            "analyze" => {
+                analyze(&args[2..]);
+                return;
+            },
+            "match" => {
+                runMatch(&args[2..]);
+                return;
+            },
+            "tournament" => {
+                runTournament(&args[2..]);
+                return;
+            },
+            "nboard" => {
+                runNBoard(&args[2..]);
+                return;
+            },
+            "gtp" => {
+                runGtp(&args[2..]);
+                return;
+            },
+            "serve" => {
+                runServer(&args[2..]);
+                return;
+            },
+            "connect" => {
+                runClient(&args[2..]);
+                return;
+            },
+            _ => {}
+        }
+    }
+
+    let mut optBoardPath: Option<String> = None;
+    let mut blackName = "human".to_string();
+    let mut whiteName = "engine".to_string();
+    let mut bConsole = false;
+    let mut bPlayersGiven = false;   // --black, --white が指定された
+    let mut animFrames: u32 = 12;
+    let mut theme = Theme::new();
+    let mut optSavePath: Option<String> = None;
+    let mut optReviewPath: Option<String> = None;
+    let mut optConnect: Option<String> = None;
+    let mut optTime: Option<u64> = None;    // 持ち時間（秒）
+    let mut increment = 0;                  // 1手ごとの増加時間（秒）
+    let mut handicap = 0;                   // 黒の駒を置いておく隅の数
+    let mut size = board::DEFAULT_SIZE;     // 盤の一辺のマス数
+
+    // othello [--console] [--black PLAYER] [--white PLAYER] [--anim-frames N]
+    //         [--theme FILE] [--sprites FILE] [--font FILE] [--no-labels]
+    //         [--save FILE] [--review FILE] [--connect HOST:PORT]
+    //         [--time SECONDS] [--increment SECONDS] [--handicap N] [--size N] [boardPath]
+    // othello analyze GAME [--depth N] [--threshold N] [--out FILE]
+    // othello match PLAYER_A PLAYER_B [--games N] [--size N]
+    // othello tournament PLAYER... [--games N] [--threads N] [--log DIR] [--size N]
+    // othello nboard [--depth N]
+    // othello gtp [--depth N]
+    // othello serve [--bind ADDR] [--time SECONDS] [--increment SECONDS] [--games N] [--size N]
+    // othello connect HOST:PORT [--player PLAYER]
+    let mut i = 1;
+    while i < args.len() {
+        match args[i].as_str() {
+            "--console" => {
+                bConsole = true;
+            },
+            "--black" if i + 1 < args.len() => {
+                i += 1;
+                blackName = args[i].to_string();
+                bPlayersGiven = true;
+            },
+            "--theme" if i + 1 < args.len() => {
+                i += 1;
+                match Theme::load(&args[i]) {
+                    Ok(t) => theme = t,
+                    Err(e) => {
+                        println!("failed to load theme: {}", e);
+                        return;
+                    }
+                }
+            },
+            "--sprites" if i + 1 < args.len() => {
+                i += 1;
+                theme.sprites = args[i].to_string();
+            },
+            "--font" if i + 1 < args.len() => {
+                i += 1;
+                theme.font = args[i].to_string();
+            },
+            "--no-labels" => {
+                theme.showLabels = false;
+            },
+            "--anim-frames" if i + 1 < args.len() => {
+                i += 1;
+                animFrames = args[i].parse::<u32>().unwrap_or(animFrames);
+            },
+            "--white" if i + 1 < args.len() => {
+                i += 1;
+                whiteName = args[i].to_string();
+                bPlayersGiven = true;
+            },
+            "--save" if i + 1 < args.len() => {
+                i += 1;
+                optSavePath = Some(args[i].to_string());
+            },
+            "--review" if i + 1 < args.len() => {
+                i += 1;
+                optReviewPath = Some(args[i].to_string());
+            },
+            "--connect" if i + 1 < args.len() => {
+                i += 1;
+                optConnect = Some(args[i].to_string());
+            },
+            "--time" if i + 1 < args.len() => {
+                i += 1;
+                optTime = args[i].parse::<u64>().ok();
+            },
+            "--increment" if i + 1 < args.len() => {
+                i += 1;
+                increment = args[i].parse::<u64>().unwrap_or(increment);
+            },
+            "--handicap" if i + 1 < args.len() => {
+                i += 1;
+                handicap = args[i].parse::<usize>().unwrap_or(handicap).min(4);
+            },
+            "--size" if i + 1 < args.len() => {
+                i += 1;
+                size = match parseSize(&args[i]) {
+                    Some(size) => size,
+                    None => {
+                        return;
+                    }
+                };
+            },
+            _ => {
+                optBoardPath = Some(args[i].to_string());
+            }
+        }
+        i += 1;
+    }
+
+    // test00(optBoardPath);
+    // test01(optBoardPath);
+    // test02(optBoardPath);
+    // test03(optBoardPath);
+    // test04(optBoardPath);
+    // test05(optBoardPath);
+
+    let optClock = optTime.map(|t| (Duration::from_secs(t), Duration::from_secs(increment)));
+    let options = GuiOptions { animFrames, theme, optSavePath, optConnect, optClock, handicap, size };
+
+    if let Some(reviewPath) = optReviewPath {
+        let gameRecord = match record::GameRecord::load(&reviewPath) {
+            Ok(r) => r,
+            Err(e) => {
+                println!("failed to load {}", e);
+                return;
+            }
+        };
+        if bConsole {
+            consoleReview(&gameRecord);
+        } else {
+            game(None, None, Some(gameRecord), options);
+        }
+        return;
+    }
+
+    if options.optConnect.is_some() {
+        game(None, None, None, options);
+        return;
+    }
+
+    if !bConsole && !bPlayersGiven {
+        // 開始画面で選ぶ
+        game(optBoardPath, None, None, options);
+        return;
+    }
+
+    let optBlack = createPlayer(&blackName, bConsole);
+    let optWhite = createPlayer(&whiteName, bConsole);
+    if optBlack.is_none() || optWhite.is_none() {
+        println!("players: human, random, greedy, engine[:depth], mcts[:iterations], level:NAME");
+        return;
+    }
+
+    if bConsole {
+        console(optBoardPath, optBlack.unwrap(), optWhite.unwrap(), options.optSavePath, options.optClock, options.handicap, options.size);
+    } else {
+        game(optBoardPath, Some((optBlack.unwrap(), optWhite.unwrap())), None, options);
+    }
+}
@@ -0,0 +1,362 @@
+#![allow(non_snake_case)]
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Piece, Pos};
+use crate::random::Rng;
+
+// 探索の打ち切り条件
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum MctsLimit {
+    Iterations(u32),    // 指定回数だけプレイアウトする
+    Time(Duration)      // 指定時間だけプレイアウトする
+}
+
+// プレイアウトで手を選ぶ方針
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Playout {
+    Random, // 置ける場所から一様に選ぶ
+    Guided  // 半分の確率でスコアの最も高い場所を選ぶ（隅を取りやすくなる）
+}
+
+// ルートの各手の統計
+#[derive(Clone, Debug, PartialEq)]
+pub struct MctsMoveStat {
+    pub pos: Option<Pos>,   // 手（Noneはパス）
+    pub visits: u32,        // 訪問回数
+    pub winRate: f64        // この手を打った側から見た勝率
+}
+
+// 探索木のノード
+#[derive(Clone, Debug)]
+struct Node {
+    mv: Option<Pos>,    // このノードに至った手（Noneはパス）
+    piece: Piece,       // このノードで次に打つ側
+    board: Board,
+    visits: u32,
+    wins: f64,          // mvを打った側（pieceの相手）から見た勝ち数（引き分けは0.5）
+    untried: Vec<Option<Pos>>,  // まだ展開していない手
+    children: Vec<Node>,
+    terminal: bool      // 双方置けない（終局）
+}
+
+impl Node {
+    fn new(board: &Board, piece: &Piece, mv: Option<Pos>) -> Self {
+        let mut untried = vec!();
+        let mut terminal = false;
+
+        let places = board.searchPos(piece);
+        if places.len() > 0 {
+            for p in &places {
+                untried.push(Some(p.pos));
+            }
+        } else if board.searchPos(&Piece::getOpponent(piece)).len() > 0 {
+            // 自分は置けないが相手は置ける → パス
+            untried.push(None);
+        } else {
+            terminal = true;
+        }
+
+        return Node {
+            mv,
+            piece: *piece,
+            board: board.clone(),
+            visits: 0,
+            wins: 0.0,
+            untried,
+            children: vec!(),
+            terminal
+        };
+    }
+
+    // UCB1値が最大の子のインデックスを返す
+    fn selectChild(&self, c: f64) -> usize {
+        let logN = (self.visits.max(1) as f64).ln();
+        let mut best = 0;
+        let mut bestValue = std::f64::MIN;
+        for (i, child) in self.children.iter().enumerate() {
+            let n = child.visits.max(1) as f64;
+            let value = child.wins / n + c * (logN / n).sqrt();
+            if value > bestValue {
+                bestValue = value;
+                best = i;
+            }
+        }
+        return best;
+    }
+
+    // 選択・展開・プレイアウト・逆伝播を1回行い、勝者を返す（引き分けはSpace）
+    fn iterate(&mut self, rng: &mut Rng, c: f64, playout: Playout) -> Piece {
+        let winner = if self.terminal {
+            getWinner(&self.board)
+        } else if self.untried.len() > 0 {
+            // 展開
+            let i = rng.nextRange(self.untried.len());
+            let mv = self.untried.swap_remove(i);
+            let nextBoard = match mv {
+                Some(pos) => self.board.put(&self.piece, &pos).unwrap().board,
+                None => self.board.clone()
+            };
+            let mut child = Node::new(&nextBoard, &Piece::getOpponent(&self.piece), mv);
+            let w = simulate(&child.board, &child.piece, rng, playout);
+            child.update(&w);
+            self.children.push(child);
+            w
+        } else {
+            // 選択
+            let i = self.selectChild(c);
+            self.children[i].iterate(rng, c, playout)
+        };
+
+        self.update(&winner);
+        return winner;
+    }
+
+    fn update(&mut self, winner: &Piece) {
+        self.visits += 1;
+        if *winner == Piece::Space {
+            self.wins += 0.5;
+        } else if *winner == Piece::getOpponent(&self.piece) {
+            self.wins += 1.0;
+        }
+    }
+}
+
+// 終局した盤の勝者を返す（引き分けはSpace）
+fn getWinner(board: &Board) -> Piece {
+    let count = board.getCount();
+    if count.nWhitePieces > count.nBlackPieces {
+        return Piece::White;
+    } else if count.nBlackPieces > count.nWhitePieces {
+        return Piece::Black;
+    } else {
+        return Piece::Space;
+    }
+}
+
+// pieceの手番から終局まで打ち進め、勝者を返す
+fn simulate(board: &Board, piece: &Piece, rng: &mut Rng, playout: Playout) -> Piece {
+    let mut board = board.clone();
+    let mut piece = *piece;
+    let mut passed = false;
+
+    loop {
+        let places = board.searchPos(&piece);
+        if places.len() == 0 {
+            if passed {
+                // 双方置けない
+                break;
+            }
+            passed = true;
+        } else {
+            passed = false;
+            let i = match playout {
+                Playout::Guided if rng.nextF64() < 0.5 => {
+                    let mut best = 0;
+                    for (j, p) in places.iter().enumerate() {
+                        if p.score > places[best].score {
+                            best = j;
+                        }
+                    }
+                    best
+                },
+                _ => rng.nextRange(places.len())
+            };
+            board = board.put(&piece, &places[i].pos).unwrap().board;
+        }
+        piece = Piece::getOpponent(&piece);
+    }
+
+    return getWinner(&board);
+}
+
+// モンテカルロ木探索(UCT)によるプレイヤー
+//
+// 打たれた手に対応する部分木は次の探索で再利用する．
+pub struct Mcts {
+    pub limit: MctsLimit,
+    pub playout: Playout,
+    pub exploration: f64,   // UCB1の探索係数
+    rng: Rng,
+    root: Option<Node>
+}
+
+impl Mcts {
+    pub fn new(limit: MctsLimit) -> Self {
+        return Mcts {
+            limit,
+            playout: Playout::Random,
+            exploration: std::f64::consts::SQRT_2,
+            rng: Rng::fromTime(),
+            root: None
+        };
+    }
+
+    pub fn setSeed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    // 探索木を捨てる
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+
+    // boardでpieceの手番の局面を探索し、最も訪問回数の多い手を返す
+    //
+    // @return 置ける場所がない場合はNone
+    pub fn search(&mut self, board: &Board, piece: &Piece) -> Option<Pos> {
+        return self.searchWithAbort(board, piece, &AtomicBool::new(false));
+    }
+
+    // searchと同じだが、abortがtrueになったら探索を打ち切る
+    pub fn searchWithAbort(&mut self, board: &Board, piece: &Piece, abort: &AtomicBool) -> Option<Pos> {
+        self.reuseRoot(board, piece);
+        let c = self.exploration;
+        let playout = self.playout;
+        let root = self.root.as_mut().unwrap();
+
+        match self.limit {
+            MctsLimit::Iterations(n) => {
+                for _ in 0..n {
+                    if abort.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    root.iterate(&mut self.rng, c, playout);
+                }
+            },
+            MctsLimit::Time(duration) => {
+                let start = Instant::now();
+                while start.elapsed() < duration && !abort.load(Ordering::Relaxed) {
+                    root.iterate(&mut self.rng, c, playout);
+                }
+            }
+        }
+
+        return match self.getMoveStats().first() {
+            Some(stat) => stat.pos,
+            // 1回もプレイアウトしなかった（回数が0か時間切れ）．パスはできないので置ける場所を返す
+            None => board.searchPos(piece).first().map(|p| p.pos)
+        };
+    }
+
+    // 前回の探索木から(board, piece)の局面を探し、あればそれをルートにする
+    //
+    // 自分の手と相手の応手の2手先まで探す．
+    fn reuseRoot(&mut self, board: &Board, piece: &Piece) {
+        let matches = |node: &Node| node.board == *board && node.piece == *piece;
+
+        if let Some(root) = self.root.take() {
+            if matches(&root) {
+                self.root = Some(root);
+                return;
+            }
+            for child in root.children {
+                if matches(&child) {
+                    self.root = Some(child);
+                    return;
+                }
+                for grandChild in child.children {
+                    if matches(&grandChild) {
+                        self.root = Some(grandChild);
+                        return;
+                    }
+                }
+            }
+        }
+
+        self.root = Some(Node::new(board, piece, None));
+    }
+
+    // ルートの各手の訪問回数と勝率を、訪問回数の多い順に返す
+    pub fn getMoveStats(&self) -> Vec<MctsMoveStat> {
+        let mut stats = vec!();
+        if let Some(root) = &self.root {
+            for child in &root.children {
+                let winRate = if child.visits > 0 { child.wins / (child.visits as f64) } else { 0.0 };
+                stats.push(MctsMoveStat {
+                    pos: child.mv,
+                    visits: child.visits,
+                    winRate
+                });
+            }
+        }
+        stats.sort_by(|a, b| b.visits.cmp(&a.visits));
+        return stats;
+    }
+
+    // ルートの総訪問回数
+    pub fn getTotalVisits(&self) -> u32 {
+        return match &self.root {
+            Some(root) => root.visits,
+            None => 0
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn startBoard() -> Board {
+        let mut board = Board::new();
+        board.init();
+        return board;
+    }
+
+    #[test]
+    fn iterationBudget() {
+        let mut mcts = Mcts::new(MctsLimit::Iterations(200));
+        mcts.setSeed(1);
+        let board = startBoard();
+        let optPos = mcts.search(&board, &Piece::Black);
+        assert_eq!(mcts.getTotalVisits(), 200);
+        let stats = mcts.getMoveStats();
+        assert_eq!(stats.len(), 4);
+        assert_eq!(stats.iter().map(|s| s.visits).sum::<u32>(), 200);
+        assert_eq!(optPos, stats[0].pos);
+        assert!(board.put(&Piece::Black, &optPos.unwrap()).is_some());
+    }
+
+    #[test]
+    fn reuseTreeAfterMoves() {
+        let mut mcts = Mcts::new(MctsLimit::Iterations(500));
+        mcts.setSeed(2);
+        let board = startBoard();
+        let pos = mcts.search(&board, &Piece::Black).unwrap();
+        let afterBlack = board.put(&Piece::Black, &pos).unwrap().board;
+
+        // 相手の応手の後の局面の訪問回数を引き継ぐ
+        let reply = afterBlack.searchPos(&Piece::White)[0].pos;
+        let afterWhite = afterBlack.put(&Piece::White, &reply).unwrap().board;
+        mcts.limit = MctsLimit::Iterations(0);
+        mcts.search(&afterWhite, &Piece::Black);
+        let inherited = mcts.getTotalVisits();
+        assert!(inherited > 0);
+
+        mcts.limit = MctsLimit::Iterations(100);
+        mcts.search(&afterWhite, &Piece::Black);
+        assert_eq!(mcts.getTotalVisits(), inherited + 100);
+
+        // 関係のない局面では作り直す
+        mcts.clear();
+        mcts.limit = MctsLimit::Iterations(0);
+        mcts.search(&board, &Piece::Black);
+        assert_eq!(mcts.getTotalVisits(), 0);
+    }
+
+    #[test]
+    fn neverPassesWithLegalMove() {
+        let board = startBoard();
+        for limit in &[MctsLimit::Iterations(0), MctsLimit::Time(Duration::from_secs(0))] {
+            let mut mcts = Mcts::new(*limit);
+            let optPos = mcts.search(&board, &Piece::Black);
+            assert!(optPos.is_some(), "{:?}", limit);
+            assert!(board.put(&Piece::Black, &optPos.unwrap()).is_some());
+        }
+
+        // 本当に置けないときだけNone
+        let mut mcts = Mcts::new(MctsLimit::Iterations(10));
+        assert_eq!(mcts.search(&Board::new(), &Piece::Black), None);
+    }
+}
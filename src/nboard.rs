@@ -0,0 +1,339 @@
+#![allow(non_snake_case)]
+
+use std::io::{BufRead, Write};
+use std::sync::atomic::AtomicBool;
+use std::time::Instant;
+
+use crate::board::{isValidSize, Board, Piece, Pos};
+use crate::record::GameRecord;
+use crate::search;
+
+// GGF形式の棋譜を読む
+//
+// BO[]で開始局面と手番、B[]とW[]で手順を表す．その他のタグは無視する．
+//
+// (ex)
+// (;GM[Othello]PC[NBoard]BO[8 ---------------------------O*------*O--------------------------- *]B[F5//1.2]W[D6];)
+pub fn parseGgf(text: &str) -> Result<GameRecord, String> {
+    let mut optRecord: Option<GameRecord> = None;
+    let mut moves = vec!();
+
+    let chars = text.chars().collect::<Vec<char>>();
+    let mut i = 0;
+    let mut tag = String::new();
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_ascii_uppercase() {
+            tag.push(c);
+        } else if c == '[' {
+            let mut value = String::new();
+            i += 1;
+            while i < chars.len() && chars[i] != ']' {
+                value.push(chars[i]);
+                i += 1;
+            }
+            match tag.as_str() {
+                "BO" => optRecord = Some(parseGgfBoard(&value)?),
+                "B" => moves.push((Piece::Black, parseGgfMove(&value)?)),
+                "W" => moves.push((Piece::White, parseGgfMove(&value)?)),
+                _ => {}
+            }
+            tag.clear();
+        } else {
+            tag.clear();
+        }
+        i += 1;
+    }
+
+    let mut record = optRecord.ok_or("BO[] not found")?;
+    for (piece, optPos) in &moves {
+        record.push(piece, *optPos);
+    }
+    return Ok(record);
+}
+
+// "8 ---...--- *"の形式の盤（先頭は一辺のマス数）と手番を読む
+fn parseGgfBoard(value: &str) -> Result<GameRecord, String> {
+    let mut it = value.split_whitespace();
+    let size = match it.next().and_then(|s| s.parse::<i32>().ok()) {
+        Some(size) if isValidSize(size) => size,
+        _ => {
+            return Err(format!("unsupported board '{}'", value));
+        }
+    };
+    let n = (size * size) as usize;
+    let cells = it.flat_map(|s| s.chars()).collect::<Vec<char>>();
+    if cells.len() != n + 1 {
+        return Err(format!("invalid board '{}'", value));
+    }
+
+    let mut board = Board::withSize(size);
+    for y in 1..=size {
+        for x in 1..=size {
+            let piece = match cells[((y - 1) * size + (x - 1)) as usize] {
+                '*' => Piece::Black,
+                'O' => Piece::White,
+                '-' => Piece::Space,
+                c => {
+                    return Err(format!("invalid character '{}' in board", c));
+                }
+            };
+            board.setPiece(x, y, piece);
+        }
+    }
+    let firstPiece = match cells[n] {
+        '*' => Piece::Black,
+        'O' => Piece::White,
+        c => {
+            return Err(format!("invalid side to move '{}'", c));
+        }
+    };
+    return Ok(GameRecord::new(&board, &firstPiece));
+}
+
+// "F5//1.2"のような手を読む（評価値と時間は無視する）．パスはNone
+fn parseGgfMove(value: &str) -> Result<Option<Pos>, String> {
+    let mv = value.split('/').next().unwrap_or("").trim();
+    if mv.eq_ignore_ascii_case("pa") {
+        return Ok(None);
+    }
+    return match Pos::fromDesc(mv) {
+        Some(pos) => Ok(Some(pos)),
+        None => Err(format!("invalid move '{}'", value))
+    };
+}
+
+fn formatMove(optPos: Option<Pos>) -> String {
+    return match optPos {
+        Some(pos) => Pos::toDesc(pos.x, pos.y),
+        None => "PA".to_string()
+    };
+}
+
+// NBoardプロトコルのエンジン
+//
+// GUIから1行ずつ命令を受け取り、応答を返す．局面は棋譜として持ち、
+// 現在の局面は棋譜を最後まで再生して求める．
+pub struct NBoardEngine {
+    pub depth: i32,
+    record: GameRecord
+}
+
+impl NBoardEngine {
+    pub fn new(depth: i32) -> Self {
+        let mut board = Board::new();
+        board.init();
+        return NBoardEngine {
+            depth,
+            record: GameRecord::new(&board, &Piece::Black)
+        };
+    }
+
+    // 現在の局面と手番
+    fn getPosition(&self) -> Result<(Board, Piece), String> {
+        let steps = self.record.replay()?;
+        let last = steps.last().unwrap();
+        return Ok((last.board.clone(), last.turn));
+    }
+
+    // 1行の命令を処理し、応答をoutに書く
+    //
+    // @return quitならfalse
+    pub fn handle(&mut self, line: &str, out: &mut dyn Write) -> std::io::Result<bool> {
+        let line = line.trim();
+        let mut it = line.splitn(2, ' ');
+        let command = it.next().unwrap_or("");
+        let args = it.next().unwrap_or("").trim();
+
+        match command {
+            "nboard" => {
+                writeln!(out, "set myname othello")?;
+            },
+            "set" => {
+                let mut it = args.splitn(2, ' ');
+                let key = it.next().unwrap_or("");
+                let value = it.next().unwrap_or("").trim();
+                match key {
+                    "depth" => {
+                        if let Ok(depth) = value.parse::<i32>() {
+                            self.depth = depth.max(1);
+                        }
+                    },
+                    "game" => {
+                        match parseGgf(value) {
+                            Ok(record) => self.record = record,
+                            Err(e) => writeln!(out, "status {}", e)?
+                        }
+                    },
+                    _ => {
+                        // contemptなどは使わない
+                    }
+                }
+            },
+            "move" => {
+                let (board, turn) = match self.getPosition() {
+                    Ok(p) => p,
+                    Err(e) => {
+                        writeln!(out, "status {}", e)?;
+                        return Ok(true);
+                    }
+                };
+                match parseGgfMove(args) {
+                    Ok(None) if board.searchPos(&turn).len() > 0 => {
+                        writeln!(out, "status cannot pass while there is a move")?;
+                    },
+                    Ok(optPos) => {
+                        self.record.push(&turn, optPos);
+                        if let Err(e) = self.record.replay() {
+                            self.record.moves.pop();
+                            writeln!(out, "status {}", e)?;
+                        }
+                    },
+                    Err(e) => writeln!(out, "status {}", e)?
+                }
+            },
+            "go" => {
+                let start = Instant::now();
+                let (optPos, score) = self.search(out)?;
+                writeln!(out, "=== {}/{}/{:.1}", formatMove(optPos), score, start.elapsed().as_secs_f64())?;
+            },
+            "hint" => {
+                let n = args.parse::<usize>().unwrap_or(1);
+                self.hint(n, out)?;
+            },
+            "learn" => {
+                // 学習はしない
+                writeln!(out, "learned")?;
+            },
+            "ping" => {
+                writeln!(out, "pong {}", args)?;
+            },
+            "quit" => {
+                return Ok(false);
+            },
+            _ => {
+                // 知らない命令は無視する
+            }
+        }
+        out.flush()?;
+        return Ok(true);
+    }
+
+    // 1手ずつ深く読み、途中経過をstatusで知らせる
+    //
+    // @return 最善手（置けない場合はNone）とそのスコア
+    fn search(&self, out: &mut dyn Write) -> std::io::Result<(Option<Pos>, i32)> {
+        let (board, turn) = match self.getPosition() {
+            Ok(p) => p,
+            Err(_) => {
+                return Ok((None, 0));
+            }
+        };
+
+        // 途中経過の書き込みに失敗したら、探索後に返す
+        let mut result = Ok(());
+        let optReport = search::iterativeSearch(&board, &turn, self.depth, None, search::search, &AtomicBool::new(false), &mut |report| {
+            if result.is_ok() {
+                result = writeln!(out, "status depth {} {} {} nodes {} nps {}",
+                    report.depth, search::formatPv(&report.pv), report.score, report.nodes, report.getNps()).and_then(|_| out.flush());
+            }
+        });
+        result?;
+        writeln!(out, "status")?;
+        return Ok(match optReport {
+            Some(report) => (report.getBestMove(), report.score),
+            None => (None, 0)
+        });
+    }
+
+    // 良い順にn手までsearch行で返す
+    fn hint(&self, n: usize, out: &mut dyn Write) -> std::io::Result<()> {
+        let (board, turn) = match self.getPosition() {
+            Ok(p) => p,
+            Err(_) => {
+                return Ok(());
+            }
+        };
+
+        writeln!(out, "status thinking")?;
+        let mut values = board.getMoveValues(&turn, self.depth);
+        values.sort_by(|a, b| b.1.cmp(&a.1));
+        if values.len() == 0 {
+            writeln!(out, "search PA 0 0 {}", self.depth)?;
+        }
+        for (pos, score) in values.iter().take(n) {
+            writeln!(out, "search {} {} 0 {}", Pos::toDesc(pos.x, pos.y), score, self.depth)?;
+        }
+        writeln!(out, "status")?;
+        return Ok(());
+    }
+}
+
+// 標準入出力などでNBoardプロトコルのエンジンとして動く
+pub fn run(input: &mut dyn BufRead, out: &mut dyn Write, depth: i32) -> std::io::Result<()> {
+    let mut engine = NBoardEngine::new(depth);
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if input.read_line(&mut line)? == 0 {
+            // 入力が閉じられた
+            break;
+        }
+        if !engine.handle(&line, out)? {
+            break;
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GAME: &str = "(;GM[Othello]PC[NBoard]DT[2014-02-21 20:52:27 GMT]PB[./mEdax]PW[chris]RE[?]TI[15:00]TY[8]\
+        BO[8 ---------------------------O*------*O--------------------------- *]B[F5//1.2]W[F6]B[E6/-3.5/0.01]W[F4];)";
+
+    #[test]
+    fn parseKnownGame() {
+        let record = parseGgf(GAME).unwrap();
+        let mut board = Board::new();
+        board.init();
+        assert_eq!(record.startBoard, board);
+        assert_eq!(record.firstPiece, Piece::Black);
+        assert_eq!(record.moves.iter().map(GameRecord::getMoveDesc).collect::<Vec<String>>(), vec!("F5", "F6", "E6", "F4"));
+        assert_eq!(record.moves[1].piece, Piece::White);
+
+        let last = record.replay().unwrap().pop().unwrap();
+        assert_eq!(last.turn, Piece::Black);
+        assert_eq!(last.board.getCount().nBlackPieces, 3);
+        assert_eq!(last.board.getCount().nWhitePieces, 5);
+    }
+
+    #[test]
+    fn parseBoardAndPass() {
+        let record = parseGgf("(;GM[Othello]BO[4 -----O*--*O----- O]W[PA];)").unwrap();
+        assert_eq!(record.startBoard.getSize(), 4);
+        assert_eq!(record.firstPiece, Piece::White);
+        assert_eq!(record.moves, vec!(crate::record::RecordMove { piece: Piece::White, pos: None }));
+
+        assert!(parseGgf("(;GM[Othello]B[F5];)").is_err());
+        assert!(parseGgf("(;GM[Othello]BO[8 --- *];)").is_err());
+        assert!(parseGgf("(;GM[Othello]BO[3 --------- *];)").is_err());
+    }
+
+    #[test]
+    fn session() {
+        let input = format!("nboard 2\nset depth 2\nset game {}\nping 1\nmove E3\ngo\nquit\nping 2\n", GAME);
+        let mut out = vec!();
+        run(&mut input.as_bytes(), &mut out, 1).unwrap();
+        let text = String::from_utf8(out).unwrap();
+        let lines = text.lines().collect::<Vec<&str>>();
+        assert_eq!(lines[0], "set myname othello");
+        assert_eq!(lines[1], "pong 1");
+        assert!(lines[2].starts_with("status depth 1 "));
+        assert!(lines.iter().any(|l| l.starts_with("status depth 2 ")));
+        assert!(lines.last().unwrap().starts_with("=== "));
+        // quitの後は読まない
+        assert!(!text.contains("pong 2"));
+    }
+}
@@ -0,0 +1,744 @@
+#![allow(non_snake_case)]
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+use crate::board::{isValidSize, Board, Count, Piece, Pos};
+use crate::clock::Clock;
+use crate::player::{Player, Move};
+
+// クライアントへの書き込みがこの時間でできなければ切断する
+// （読まない観戦者がいても対局が止まらないように）
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+// playRemoteで手が続けてこの回数だけ拒否されたら投了する
+const MAX_MOVE_ERRORS: u32 = 3;
+
+// 対局サーバーのプロトコル
+//
+// 1行に1つのメッセージを書く．
+//
+// クライアント → サーバー
+//   HELLO name             名乗る（省略可）
+//   MOVE F5                打つ（パスはサーバーが自動で行う）
+//   RESIGN                 投了する
+//
+// サーバー → クライアント
+//   WELCOME Black          役割（Black, White, Spectator）
+//   BOARD XO--...- Black   盤（8x8なら64文字、黒がX、白がO）と手番（終局していればSpace）
+//   CLOCK 59000 60000      黒と白の残り時間（ミリ秒）
+//   MOVE Black F5          打たれた手（パスはpass）
+//   ERROR text             直前のメッセージの誤り
+//   GAMEOVER 40 24 Black end   黒と白の駒の数、勝者（引き分けはDraw）、理由（end, time, resign, disconnect）
+
+// サーバーから送られるメッセージ
+#[derive(Clone, Debug, PartialEq)]
+pub enum ServerMessage {
+    Welcome(Piece),                 // 役割（観戦者はSpace）
+    Board(Board, Piece),            // 盤と手番
+    Clock(Duration, Duration),      // 黒と白の残り時間
+    Move(Piece, Option<Pos>),       // 打たれた手（Noneはパス）
+    Error(String),
+    GameOver(Count, Piece, String)  // 駒の数、勝者（引き分けはSpace）、理由
+}
+
+fn pieceToRole(piece: &Piece) -> &str {
+    return match piece {
+        Piece::Black => "Black",
+        Piece::White => "White",
+        Piece::Space => "Spectator"
+    };
+}
+
+fn parseRole(s: &str) -> Option<Piece> {
+    return match s {
+        "Black" => Some(Piece::Black),
+        "White" => Some(Piece::White),
+        "Spectator" | "Space" | "Draw" => Some(Piece::Space),
+        _ => None
+    };
+}
+
+fn formatPos(optPos: &Option<Pos>) -> String {
+    return match optPos {
+        Some(pos) => Pos::toDesc(pos.x, pos.y),
+        None => "pass".to_string()
+    };
+}
+
+fn parsePos(s: &str) -> Option<Option<Pos>> {
+    if s.eq_ignore_ascii_case("pass") {
+        return Some(None);
+    }
+    return Pos::fromDesc(s).map(Some);
+}
+
+impl ServerMessage {
+    pub fn format(&self) -> String {
+        return match self {
+            ServerMessage::Welcome(piece) => format!("WELCOME {}", pieceToRole(piece)),
+            ServerMessage::Board(board, turn) => {
+                let mut cells = String::new();
+                let size = board.getSize();
+                for y in 1..=size {
+                    for x in 1..=size {
+                        cells.push(match board.getPiece(x, y) {
+                            Some(Piece::Black) => 'X',
+                            Some(Piece::White) => 'O',
+                            _ => '-'
+                        });
+                    }
+                }
+                let turnName = if *turn == Piece::Space { "Space" } else { pieceToRole(turn) };
+                format!("BOARD {} {}", cells, turnName)
+            },
+            ServerMessage::Clock(black, white) => format!("CLOCK {} {}", black.as_millis(), white.as_millis()),
+            ServerMessage::Move(piece, optPos) => format!("MOVE {} {}", pieceToRole(piece), formatPos(optPos)),
+            ServerMessage::Error(text) => format!("ERROR {}", text),
+            ServerMessage::GameOver(count, winner, reason) => {
+                let winnerName = if *winner == Piece::Space { "Draw" } else { pieceToRole(winner) };
+                format!("GAMEOVER {} {} {} {}", count.nBlackPieces, count.nWhitePieces, winnerName, reason)
+            }
+        };
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        if words.len() == 0 {
+            return None;
+        }
+        match (words[0], words.len()) {
+            ("WELCOME", 2) => {
+                return parseRole(words[1]).map(ServerMessage::Welcome);
+            },
+            ("BOARD", 3) => {
+                // 文字数から盤の大きさを求める
+                let cells = words[1].chars().collect::<Vec<char>>();
+                let size = (cells.len() as f64).sqrt().round() as i32;
+                if ((size * size) as usize != cells.len()) || !isValidSize(size) {
+                    return None;
+                }
+                let mut board = Board::withSize(size);
+                for y in 1..=size {
+                    for x in 1..=size {
+                        let piece = match cells[((y - 1) * size + (x - 1)) as usize] {
+                            'X' => Piece::Black,
+                            'O' => Piece::White,
+                            _ => Piece::Space
+                        };
+                        board.setPiece(x, y, piece);
+                    }
+                }
+                return parseRole(words[2]).map(|turn| ServerMessage::Board(board, turn));
+            },
+            ("CLOCK", 3) => {
+                let black = words[1].parse::<u64>().ok()?;
+                let white = words[2].parse::<u64>().ok()?;
+                return Some(ServerMessage::Clock(Duration::from_millis(black), Duration::from_millis(white)));
+            },
+            ("MOVE", 3) => {
+                let piece = parseRole(words[1])?;
+                return parsePos(words[2]).map(|optPos| ServerMessage::Move(piece, optPos));
+            },
+            ("ERROR", _) => {
+                return Some(ServerMessage::Error(words[1..].join(" ")));
+            },
+            ("GAMEOVER", 5) => {
+                let count = Count {
+                    nBlackPieces: words[1].parse::<i32>().ok()?,
+                    nWhitePieces: words[2].parse::<i32>().ok()?
+                };
+                let winner = parseRole(words[3])?;
+                return Some(ServerMessage::GameOver(count, winner, words[4].to_string()));
+            },
+            _ => {
+                return None;
+            }
+        }
+    }
+}
+
+// クライアントから送られるメッセージ
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClientMessage {
+    Hello(String),
+    Move(Option<Pos>),
+    Resign
+}
+
+impl ClientMessage {
+    pub fn format(&self) -> String {
+        return match self {
+            ClientMessage::Hello(name) => format!("HELLO {}", name),
+            ClientMessage::Move(optPos) => format!("MOVE {}", formatPos(optPos)),
+            ClientMessage::Resign => "RESIGN".to_string()
+        };
+    }
+
+    pub fn parse(line: &str) -> Option<Self> {
+        let words = line.split_whitespace().collect::<Vec<&str>>();
+        if words.len() == 0 {
+            return None;
+        }
+        return match (words[0].to_uppercase().as_str(), words.len()) {
+            ("HELLO", _) => Some(ClientMessage::Hello(words[1..].join(" "))),
+            ("MOVE", 2) => parsePos(words[1]).map(ClientMessage::Move),
+            ("RESIGN", 1) => Some(ClientMessage::Resign),
+            _ => None
+        };
+    }
+}
+
+// サーバーの設定
+#[derive(Clone, Debug, PartialEq)]
+pub struct ServerOptions {
+    pub optClock: Option<(Duration, Duration)>,     // 持ち時間と1手ごとの増加時間（Noneは無制限）
+    pub nGames: u32,    // この数だけ対局したら終わる（0は無制限）
+    pub size: i32       // 盤の一辺のマス数
+}
+
+// 接続ごとのスレッドから対局のスレッドへの知らせ
+enum Event {
+    Connected(usize, TcpStream),
+    Line(usize, String),
+    Disconnected(usize)
+}
+
+// 対局サーバーの状態
+struct Server {
+    options: ServerOptions,
+    clients: HashMap<usize, (TcpStream, String)>,   // 接続IDごとの書き込み先と名前
+    players: [Option<usize>; 2],    // 黒、白の接続ID
+    board: Board,
+    turn: Piece,        // 手番（対局中でなければSpace）
+    optClock: Option<Clock>,
+    nGamesPlayed: u32
+}
+
+impl Server {
+    fn getSlot(piece: &Piece) -> usize {
+        return if *piece == Piece::White { 1 } else { 0 };
+    }
+
+    fn send(&mut self, id: usize, message: &ServerMessage) {
+        let mut bFailed = false;
+        if let Some((stream, _)) = self.clients.get_mut(&id) {
+            bFailed = writeln!(stream, "{}", message.format()).is_err();
+        }
+        if bFailed {
+            // 接続を閉じれば、読み込み側のスレッドがDisconnectedを知らせてくる
+            if let Some((stream, _)) = self.clients.remove(&id) {
+                let _ = stream.shutdown(Shutdown::Both);
+            }
+        }
+    }
+
+    fn broadcast(&mut self, message: &ServerMessage) {
+        let ids = self.clients.keys().cloned().collect::<Vec<usize>>();
+        for id in ids {
+            self.send(id, message);
+        }
+    }
+
+    fn getClockMessage(&self) -> Option<ServerMessage> {
+        return self.optClock.as_ref().map(|clock| {
+            ServerMessage::Clock(clock.getRemaining(&Piece::Black), clock.getRemaining(&Piece::White))
+        });
+    }
+
+    // 時計と盤を全員に知らせる（クライアントが盤を受け取って考え始めるときに時計が新しいように、時計が先）
+    fn broadcastState(&mut self) {
+        if let Some(message) = self.getClockMessage() {
+            self.broadcast(&message);
+        }
+        let message = ServerMessage::Board(self.board.clone(), self.turn);
+        self.broadcast(&message);
+    }
+
+    fn getName(&self, id: usize) -> String {
+        return self.clients.get(&id).map_or(String::new(), |c| c.1.clone());
+    }
+
+    fn onConnected(&mut self, id: usize, stream: TcpStream, log: &mut dyn FnMut(&str)) {
+        self.clients.insert(id, (stream, format!("client{}", id)));
+        let role = if self.players[0].is_none() {
+            self.players[0] = Some(id);
+            Piece::Black
+        } else if self.players[1].is_none() {
+            self.players[1] = Some(id);
+            Piece::White
+        } else {
+            Piece::Space
+        };
+        log(&format!("client{} connected as {}", id, pieceToRole(&role)));
+
+        self.send(id, &ServerMessage::Welcome(role));
+        if let Some(message) = self.getClockMessage() {
+            self.send(id, &message);
+        }
+        let message = ServerMessage::Board(self.board.clone(), self.turn);
+        self.send(id, &message);
+    }
+
+    fn onDisconnected(&mut self, id: usize, log: &mut dyn FnMut(&str)) {
+        log(&format!("{} disconnected", self.getName(id)));
+        self.clients.remove(&id);
+        for slot in 0..2 {
+            if self.players[slot] == Some(id) {
+                self.players[slot] = None;
+                if self.turn != Piece::Space {
+                    // 残った側の勝ち
+                    let winner = if slot == 0 { Piece::White } else { Piece::Black };
+                    self.endGame(winner, "disconnect", log);
+                }
+            }
+        }
+    }
+
+    fn onLine(&mut self, id: usize, line: &str, log: &mut dyn FnMut(&str)) {
+        let message = match ClientMessage::parse(line) {
+            Some(m) => m,
+            None => {
+                self.send(id, &ServerMessage::Error(format!("unknown message '{}'", line.trim())));
+                return;
+            }
+        };
+
+        let optSlot = (0..2).find(|&slot| self.players[slot] == Some(id));
+        match message {
+            ClientMessage::Hello(name) => {
+                if let Some(client) = self.clients.get_mut(&id) {
+                    client.1 = name;
+                }
+            },
+            ClientMessage::Resign => {
+                if let (Some(slot), true) = (optSlot, self.turn != Piece::Space) {
+                    let winner = if slot == 0 { Piece::White } else { Piece::Black };
+                    self.endGame(winner, "resign", log);
+                } else {
+                    self.send(id, &ServerMessage::Error("not playing".to_string()));
+                }
+            },
+            ClientMessage::Move(optPos) => {
+                if self.turn == Piece::Space || optSlot != Some(Server::getSlot(&self.turn)) {
+                    self.send(id, &ServerMessage::Error("not your turn".to_string()));
+                    return;
+                }
+                let pos = match optPos {
+                    Some(pos) => pos,
+                    None => {
+                        self.send(id, &ServerMessage::Error("cannot pass while there is a move".to_string()));
+                        return;
+                    }
+                };
+                let ret = match self.board.put(&self.turn, &pos) {
+                    Some(ret) => ret,
+                    None => {
+                        self.send(id, &ServerMessage::Error(format!("illegal move {}", Pos::toDesc(pos.x, pos.y))));
+                        return;
+                    }
+                };
+                if let Some(clock) = &mut self.optClock {
+                    if !clock.punch() {
+                        let winner = Piece::getOpponent(&self.turn);
+                        self.endGame(winner, "time", log);
+                        return;
+                    }
+                }
+
+                log(&format!("{} ({}) put on {}", self.getName(id), pieceToRole(&self.turn), Pos::toDesc(pos.x, pos.y)));
+                self.board = ret.board;
+                let message = ServerMessage::Move(self.turn, Some(pos));
+                self.broadcast(&message);
+                self.turn = Piece::getOpponent(&self.turn);
+                self.advance(log);
+            }
+        }
+    }
+
+    // 時間切れを調べる
+    fn onTick(&mut self, log: &mut dyn FnMut(&str)) {
+        if self.turn == Piece::Space {
+            return;
+        }
+        let bFlagged = self.optClock.as_ref().map_or(false, |clock| clock.isFlagged(&self.turn));
+        if bFlagged {
+            let winner = Piece::getOpponent(&self.turn);
+            self.endGame(winner, "time", log);
+        }
+    }
+
+    fn startGame(&mut self, log: &mut dyn FnMut(&str)) {
+        log(&format!("game {} started: {} (Black) vs {} (White)",
+            self.nGamesPlayed + 1,
+            self.getName(self.players[0].unwrap()),
+            self.getName(self.players[1].unwrap())));
+        self.board = Board::withSize(self.options.size);
+        self.board.init();
+        self.turn = Piece::Black;
+        if let Some(clock) = &mut self.optClock {
+            clock.reset();
+        }
+        self.advance(log);
+    }
+
+    // 手番の側が置けなければパスし、双方置けなければ終局にする．
+    // 続けられる場合は手番の側の時計を動かし、盤を知らせる
+    fn advance(&mut self, log: &mut dyn FnMut(&str)) {
+        if self.board.searchPos(&self.turn).len() == 0 {
+            let opponent = Piece::getOpponent(&self.turn);
+            if self.board.searchPos(&opponent).len() == 0 {
+                let count = self.board.getCount();
+                let winner = if count.nBlackPieces > count.nWhitePieces {
+                    Piece::Black
+                } else if count.nWhitePieces > count.nBlackPieces {
+                    Piece::White
+                } else {
+                    Piece::Space
+                };
+                self.endGame(winner, "end", log);
+                return;
+            }
+            let message = ServerMessage::Move(self.turn, None);
+            self.broadcast(&message);
+            self.turn = opponent;
+        }
+
+        if let Some(clock) = &mut self.optClock {
+            clock.start(&self.turn);
+        }
+        self.broadcastState();
+    }
+
+    fn endGame(&mut self, winner: Piece, reason: &str, log: &mut dyn FnMut(&str)) {
+        self.turn = Piece::Space;
+        if let Some(clock) = &mut self.optClock {
+            clock.pause();
+        }
+        let count = self.board.getCount();
+        self.broadcastState();
+        let message = ServerMessage::GameOver(count.clone(), winner, reason.to_string());
+        self.broadcast(&message);
+        log(&message.format());
+
+        // 次の対局は先後を入れ替える
+        self.nGamesPlayed += 1;
+        self.players.swap(0, 1);
+        for slot in 0..2 {
+            if let Some(id) = self.players[slot] {
+                let role = if slot == 0 { Piece::Black } else { Piece::White };
+                self.send(id, &ServerMessage::Welcome(role));
+            }
+        }
+    }
+
+    fn isFinished(&self) -> bool {
+        return self.options.nGames > 0 && self.nGamesPlayed >= self.options.nGames;
+    }
+}
+
+// listenerで接続を受け付け、最初の2人を黒と白として対局させる
+//
+// 3人目以降は観戦者として盤の更新を受け取る．対局が終わると先後を入れ替えて
+// 次の対局を始める．logには接続や打たれた手などの記録が渡される．
+pub fn serve(listener: TcpListener, options: &ServerOptions, log: &mut dyn FnMut(&str)) -> std::io::Result<()> {
+    let (tx, rx) = channel();
+
+    thread::spawn(move || {
+        let mut nextId = 1;
+        for optStream in listener.incoming() {
+            let stream = match optStream {
+                Ok(s) => s,
+                Err(_) => {
+                    continue;
+                }
+            };
+            let writer = match stream.try_clone().and_then(|w| w.set_write_timeout(Some(WRITE_TIMEOUT)).map(|_| w)) {
+                Ok(w) => w,
+                Err(_) => {
+                    continue;
+                }
+            };
+            let id = nextId;
+            nextId += 1;
+            if tx.send(Event::Connected(id, writer)).is_err() {
+                break;
+            }
+
+            let tx = tx.clone();
+            thread::spawn(move || {
+                for optLine in BufReader::new(stream).lines() {
+                    match optLine {
+                        Ok(line) => {
+                            if tx.send(Event::Line(id, line)).is_err() {
+                                return;
+                            }
+                        },
+                        Err(_) => {
+                            break;
+                        }
+                    }
+                }
+                let _ = tx.send(Event::Disconnected(id));
+            });
+        }
+    });
+
+    let mut board = Board::withSize(options.size);
+    board.init();
+    let mut server = Server {
+        options: options.clone(),
+        clients: HashMap::new(),
+        players: [None, None],
+        board,
+        turn: Piece::Space,
+        optClock: options.optClock.map(|(initial, increment)| Clock::new(initial, increment)),
+        nGamesPlayed: 0
+    };
+
+    while !server.isFinished() {
+        match rx.recv_timeout(Duration::from_millis(100)) {
+            Ok(Event::Connected(id, stream)) => server.onConnected(id, stream, log),
+            Ok(Event::Line(id, line)) => server.onLine(id, &line, log),
+            Ok(Event::Disconnected(id)) => server.onDisconnected(id, log),
+            Err(RecvTimeoutError::Timeout) => {},
+            Err(RecvTimeoutError::Disconnected) => {
+                break;
+            }
+        }
+        server.onTick(log);
+
+        if server.turn == Piece::Space && !server.isFinished() && server.players[0].is_some() && server.players[1].is_some() {
+            server.startGame(log);
+        }
+    }
+
+    // 読み込み側のスレッドも終わるように接続を閉じる
+    for (stream, _) in server.clients.values() {
+        let _ = stream.shutdown(Shutdown::Both);
+    }
+    return Ok(());
+}
+
+// UIから使うサーバーへの接続
+//
+// 受け取ったメッセージは別スレッドで読んでおき、tryRecvで取り出す．
+pub struct Connection {
+    writer: TcpStream,
+    rx: Receiver<ServerMessage>
+}
+
+impl Connection {
+    pub fn connect(addr: &str, name: &str) -> std::io::Result<Self> {
+        let stream = TcpStream::connect(addr)?;
+        let mut writer = stream.try_clone()?;
+        writeln!(writer, "{}", ClientMessage::Hello(name.to_string()).format())?;
+
+        let (tx, rx) = channel();
+        thread::spawn(move || {
+            for optLine in BufReader::new(stream).lines() {
+                let line = match optLine {
+                    Ok(l) => l,
+                    Err(_) => {
+                        break;
+                    }
+                };
+                if let Some(message) = ServerMessage::parse(&line) {
+                    if tx.send(message).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+
+        return Ok(Connection { writer, rx });
+    }
+
+    pub fn send(&mut self, message: &ClientMessage) -> std::io::Result<()> {
+        return writeln!(self.writer, "{}", message.format());
+    }
+
+    // 届いているメッセージを1つ取り出す
+    //
+    // @return 届いていなければErr(Empty)、接続が閉じられたらErr(Disconnected)
+    pub fn tryRecv(&self) -> Result<ServerMessage, TryRecvError> {
+        return self.rx.try_recv();
+    }
+}
+
+// サーバーに接続し、playerに打たせる（エンジンやコンソールの人間用）
+//
+// 接続が閉じられるまで続ける．logには受け取ったメッセージが渡される．
+// 同じ局面で手がMAX_MOVE_ERRORS回続けて拒否されたら投了する．
+pub fn playRemote(addr: &str, player: &mut dyn Player, log: &mut dyn FnMut(&str)) -> std::io::Result<()> {
+    let stream = TcpStream::connect(addr)?;
+    let mut writer = stream.try_clone()?;
+    writeln!(writer, "{}", ClientMessage::Hello(player.getName()).format())?;
+
+    let mut myPiece = Piece::Space;
+    let mut optPosition: Option<(Board, Piece)> = None;   // 最後に知らされた局面
+    let mut nErrors = 0;    // 最後に知らされた局面で拒否された回数
+    for optLine in BufReader::new(stream).lines() {
+        let line = optLine?;
+        log(&line);
+        let mut bAsk = false;
+        match ServerMessage::parse(&line) {
+            Some(ServerMessage::Welcome(piece)) => {
+                myPiece = piece;
+                optPosition = None;
+                player.reset();
+            },
+            Some(ServerMessage::Board(board, turn)) => {
+                // 同じ局面を何度も考えない
+                let position = (board, turn);
+                bAsk = optPosition.as_ref() != Some(&position);
+                if bAsk {
+                    nErrors = 0;
+                }
+                optPosition = Some(position);
+            },
+            Some(ServerMessage::Clock(black, white)) => {
+                // 増加時間は知らされないので0とする
+                let remaining = if myPiece == Piece::White { white } else { black };
+                player.setTimeLeft(remaining, Duration::from_secs(0));
+            },
+            Some(ServerMessage::Error(_)) => {
+                // 打てなかったのでもう一度考えさせる
+                nErrors += 1;
+                bAsk = true;
+            },
+            _ => {}
+        }
+
+        if let (true, Some((board, turn))) = (bAsk, &optPosition) {
+            if *turn != myPiece || myPiece == Piece::Space {
+                continue;
+            }
+            if nErrors >= MAX_MOVE_ERRORS {
+                // 打てる手を返せないので、同じことを繰り返さずに投了する
+                writeln!(writer, "{}", ClientMessage::Resign.format())?;
+                continue;
+            }
+            match player.getMove(board, turn) {
+                Some(Move::Put(pos)) => {
+                    writeln!(writer, "{}", ClientMessage::Move(Some(pos)).format())?;
+                },
+                _ => {
+                    writeln!(writer, "{}", ClientMessage::Resign.format())?;
+                }
+            }
+        }
+    }
+    return Ok(());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::player::RandomPlayer;
+
+    #[test]
+    fn serverMessages() {
+        let mut board = Board::withSize(6);
+        board.init();
+        board.setPiece(1, 6, Piece::Black);
+        let messages = vec!(
+            ServerMessage::Welcome(Piece::Black),
+            ServerMessage::Welcome(Piece::Space),
+            ServerMessage::Board(board.clone(), Piece::White),
+            ServerMessage::Board(Board::new(), Piece::Space),
+            ServerMessage::Clock(Duration::from_millis(59000), Duration::from_millis(60000)),
+            ServerMessage::Move(Piece::Black, Pos::fromDesc("F5")),
+            ServerMessage::Move(Piece::White, None),
+            ServerMessage::Error("illegal move A1".to_string()),
+            ServerMessage::GameOver(Count { nBlackPieces: 32, nWhitePieces: 32 }, Piece::Space, "end".to_string())
+        );
+        for message in &messages {
+            assert_eq!(ServerMessage::parse(&message.format()).as_ref(), Some(message));
+        }
+        assert_eq!(messages[2].format(), format!("BOARD {}{}{}{}{}{} White", "------", "------", "--OX--", "--XO--", "------", "X-----"));
+        assert_eq!(messages[8].format(), "GAMEOVER 32 32 Draw end");
+
+        assert_eq!(ServerMessage::parse(""), None);
+        assert_eq!(ServerMessage::parse("BOARD XO- Black"), None);
+        assert_eq!(ServerMessage::parse("MOVE Black Z9"), None);
+    }
+
+    #[test]
+    fn clientMessages() {
+        let messages = vec!(
+            ClientMessage::Hello("engine(depth 5)".to_string()),
+            ClientMessage::Move(Pos::fromDesc("C4")),
+            ClientMessage::Move(None),
+            ClientMessage::Resign
+        );
+        for message in &messages {
+            assert_eq!(ClientMessage::parse(&message.format()).as_ref(), Some(message));
+        }
+        assert_eq!(ClientMessage::parse("move c4"), Some(messages[1].clone()));
+        assert_eq!(ClientMessage::parse("MOVE"), None);
+        assert_eq!(ClientMessage::parse("JUMP"), None);
+    }
+
+    // 常に埋まっているマスに打とうとする
+    struct IllegalPlayer;
+
+    impl Player for IllegalPlayer {
+        fn getName(&self) -> String {
+            return "illegal".to_string();
+        }
+
+        fn getMove(&mut self, _board: &Board, _piece: &Piece) -> Option<Move> {
+            return Some(Move::Put(Pos { x: 2, y: 2 }));
+        }
+    }
+
+    // 4x4の盤で1局だけ対局させ、サーバーとクライアントのログを返す
+    fn playGame(players: Vec<Box<dyn Player>>) -> (Vec<String>, Vec<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let options = ServerOptions { optClock: None, nGames: 1, size: 4 };
+        let server = thread::spawn(move || {
+            let mut lines = vec!();
+            serve(listener, &options, &mut |line| lines.push(line.to_string())).unwrap();
+            return lines;
+        });
+
+        let clients = players.into_iter().map(|mut player| {
+            let addr = addr.clone();
+            return thread::spawn(move || {
+                let mut lines = vec!();
+                playRemote(&addr, &mut *player, &mut |line| lines.push(line.to_string())).unwrap();
+                return lines;
+            });
+        }).collect::<Vec<_>>();
+
+        let serverLog = server.join().unwrap();
+        return (serverLog, clients.into_iter().map(|client| client.join().unwrap()).collect());
+    }
+
+    #[test]
+    fn playOverTcp() {
+        let (serverLog, clientLogs) = playGame(vec!(Box::new(RandomPlayer::new(1)), Box::new(RandomPlayer::new(2))));
+        let gameOver = serverLog.last().unwrap().clone();
+        assert!(gameOver.starts_with("GAMEOVER "), "{:?}", serverLog);
+        assert!(serverLog.iter().any(|l| l.contains(" put on ")));
+        for lines in &clientLogs {
+            assert!(lines.iter().any(|l| l == "WELCOME Black" || l == "WELCOME White"));
+            assert!(lines.contains(&gameOver), "{:?}", lines);
+            assert!(!lines.iter().any(|l| l.starts_with("ERROR")), "{:?}", lines);
+        }
+    }
+
+    #[test]
+    fn resignAfterRepeatedErrors() {
+        let (serverLog, clientLogs) = playGame(vec!(Box::new(IllegalPlayer), Box::new(RandomPlayer::new(1))));
+        assert!(serverLog.last().unwrap().ends_with(" resign"), "{:?}", serverLog);
+        let nErrors = clientLogs.iter().map(|lines| lines.iter().filter(|l| l.starts_with("ERROR")).count()).sum::<usize>();
+        assert_eq!(nErrors, MAX_MOVE_ERRORS as usize);
+    }
+}
@@ -0,0 +1,513 @@
+#![allow(non_snake_case)]
+
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::time::Duration;
+
+use crate::board::{Board, Piece, Pos, SearchStop};
+use crate::clock::allocateTime;
+use crate::logging;
+use crate::mcts::{Mcts, MctsLimit};
+use crate::random::Rng;
+use crate::search::{self, SearchReport};
+
+// プレイヤーの手
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Move {
+    Put(Pos),   // posに置く
+    Pass        // パス
+}
+
+// 探索の途中経過を受け取る関数（Player::setOnProgress）
+pub type ProgressFn = Box<dyn FnMut(&SearchReport) + Send>;
+
+// 対局者（人間、エンジンなど）
+//
+// エンジンはUIとは別のスレッドで考えさせるのでSendが必要．
+pub trait Player: Send {
+    // 表示用の名前
+    fn getName(&self) -> String;
+
+    // boardでpieceの手番の手を返す
+    //
+    // 人間の入力待ちなど、まだ手が決まっていない場合はNoneを返す．
+    // その場合は呼び出し側が後でもう一度呼ぶ．
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move>;
+
+    // UIで選ばれた位置を受け取る（人間のプレイヤー用）
+    fn setInput(&mut self, _pos: &Pos) {}
+
+    // 新しい対局を始める
+    fn reset(&mut self) {}
+
+    // 人間が操作するプレイヤーならtrue
+    fn isHuman(&self) -> bool {
+        return false;
+    }
+
+    // 探索を中断するためのフラグを設定する（エンジン用）
+    //
+    // フラグがtrueになったら、getMoveはできるだけ早く戻る．
+    fn setAbortFlag(&mut self, _abort: Arc<AtomicBool>) {}
+
+    // 持ち時間を知らせる（時計を使う対局で、getMoveの前に呼ばれる）
+    //
+    // エンジンはこれを元に1手に使う時間を決める．
+    fn setTimeLeft(&mut self, _remaining: Duration, _increment: Duration) {}
+
+    // 直前に返した手の評価（表示用）．評価しないプレイヤーはNone
+    fn getEvaluation(&self) -> Option<String> {
+        return None;
+    }
+
+    // 直前に返した手の探索の結果（表示用）．探索しないプレイヤーはNone
+    fn getLastReport(&self) -> Option<SearchReport> {
+        return None;
+    }
+
+    // 探索の途中経過（読み切った深さごとの結果）を受け取る関数を設定する（エンジン用）
+    //
+    // getMoveを呼んだスレッドから呼ばれる．
+    fn setOnProgress(&mut self, _onProgress: ProgressFn) {}
+}
+
+// 置ける場所からランダムに選ぶ
+pub struct RandomPlayer {
+    rng: Rng
+}
+
+impl RandomPlayer {
+    pub fn new(seed: u64) -> Self {
+        return RandomPlayer { rng: Rng::new(seed) };
+    }
+}
+
+impl Player for RandomPlayer {
+    fn getName(&self) -> String {
+        return "random".to_string();
+    }
+
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move> {
+        let places = board.searchPos(piece);
+        if places.len() == 0 {
+            return Some(Move::Pass);
+        }
+        let i = self.rng.nextRange(places.len());
+        return Some(Move::Put(places[i].pos));
+    }
+}
+
+// 最も多く取れる場所を選ぶ
+pub struct GreedyPlayer;
+
+impl Player for GreedyPlayer {
+    fn getName(&self) -> String {
+        return "greedy".to_string();
+    }
+
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move> {
+        let places = board.searchPos(piece);
+        return match places.iter().max_by_key(|&elem| elem.ntake) {
+            Some(best) => Some(Move::Put(best.pos)),
+            None => Some(Move::Pass)
+        };
+    }
+}
+
+// 同じ局面でも毎回同じ手にならないように、手の選び方にばらつきを入れる
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Randomness {
+    None,           // 最もスコアの高い手（同点なら決まった手）
+    TieBreak,       // 最もスコアの高い手の中からランダムに選ぶ
+    Softmax(f64)    // スコアの差を温度で割ったsoftmaxの確率で選ぶ（温度が高いほどばらつく）
+}
+
+// 評価値の付いた手からrandomnessに従って1つ選ぶ
+//
+// @return valuesが空ならNone
+pub fn selectMove(values: &[(Pos, i32)], randomness: Randomness, rng: &mut Rng) -> Option<(Pos, i32)> {
+    let maxValue = values.iter().map(|v| v.1).max()?;
+    match randomness {
+        Randomness::Softmax(temperature) if temperature > 0.0 => {
+            let weights = values.iter().map(|v| ((v.1 - maxValue) as f64 / temperature).exp()).collect::<Vec<f64>>();
+            let mut r = rng.nextF64() * weights.iter().sum::<f64>();
+            for (i, w) in weights.iter().enumerate() {
+                if r < *w {
+                    return Some(values[i]);
+                }
+                r -= w;
+            }
+            return values.last().cloned();
+        },
+        Randomness::None => {
+            return values.iter().find(|v| v.1 == maxValue).cloned();
+        },
+        _ => {
+            // 温度が0以下のsoftmaxも同点の中から選ぶ
+            let best = values.iter().filter(|v| v.1 == maxValue).collect::<Vec<&(Pos, i32)>>();
+            return Some(*best[rng.nextRange(best.len())]);
+        }
+    }
+}
+
+// スコア計算用の係数の決め方
+#[derive(Clone, Debug, PartialEq)]
+pub enum Evaluation {
+    Positional,         // 盤の既定値（隅を高く、隅の隣を低く）
+    Disc,               // すべて1（駒の数だけで評価する）
+    Weights(Vec<i32>)   // 左上から行ごとに指定する（盤の大きさと合わなければ既定値）
+}
+
+// getBestMoveと同じ探索（search::searchBestMove）をする
+//
+// depthを上限として1手ずつ深く読み直し（反復深化）、optTimeや時計の時間を
+// 使い切りそうになったらそれまでの結果を使う．
+// randomnessを指定すると、置ける場所をすべて読み（search::search）、手ごとのスコアから選ぶ．
+pub struct EnginePlayer {
+    pub depth: i32,
+    pub optTime: Option<Duration>,      // 1手に使う時間
+    pub evaluation: Evaluation,
+    pub randomness: Randomness,
+    rng: Rng,
+    optTimeLeft: Option<(Duration, Duration)>,  // 時計の残り時間と増加時間
+    abort: Arc<AtomicBool>,
+    lastReport: Option<SearchReport>,   // 直前の探索の結果
+    optOnProgress: Option<ProgressFn>
+}
+
+impl EnginePlayer {
+    pub fn new(depth: i32) -> Self {
+        return EnginePlayer {
+            depth,
+            optTime: None,
+            evaluation: Evaluation::Positional,
+            randomness: Randomness::None,
+            rng: Rng::fromTime(),
+            optTimeLeft: None,
+            abort: Arc::new(AtomicBool::new(false)),
+            lastReport: None,
+            optOnProgress: None
+        };
+    }
+
+    // randomnessで使う乱数のseedを決める（同じseedなら同じ手を選ぶ）
+    pub fn setSeed(&mut self, seed: u64) {
+        self.rng = Rng::new(seed);
+    }
+
+    // 1手に使える時間（optTimeと時計から割り当てた時間の短い方）
+    fn getTimeLimit(&self, board: &Board) -> Option<Duration> {
+        let optBudget = self.optTimeLeft.map(|(remaining, increment)| allocateTime(remaining, increment, board.getEmptyCount()));
+        return match (self.optTime, optBudget) {
+            (Some(t), Some(b)) => Some(t.min(b)),
+            (t, b) => t.or(b)
+        };
+    }
+
+    // 探索し、最後に読み切った深さの結果を返す
+    //
+    // 時間の制限の時刻を過ぎたら読んでいる途中でも打ち切る（深さ1だけは読み切る）．
+    // 結果の局面の数と時間は深さ1からの合計．
+    fn search(&mut self, board: &Board, piece: &Piece) -> Option<SearchReport> {
+        let optTime = self.getTimeLimit(board);
+        // ばらつきを入れるなら、最善でない手のスコアも要るのですべての手を読む
+        let searchDepth: fn(&Board, &Piece, i32, &SearchStop) -> SearchReport = if self.randomness == Randomness::None {
+            search::searchBestMove
+        } else {
+            search::search
+        };
+        let optOnProgress = &mut self.optOnProgress;
+        return search::iterativeSearch(board, piece, self.depth, optTime, searchDepth, &self.abort, &mut |report| {
+            if let Some(onProgress) = optOnProgress {
+                onProgress(report);
+            }
+        });
+    }
+}
+
+impl Player for EnginePlayer {
+    fn getName(&self) -> String {
+        return format!("engine(depth {})", self.depth);
+    }
+
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move> {
+        self.lastReport = None;
+        let mut board = board.clone();
+        match &self.evaluation {
+            Evaluation::Positional => {},
+            Evaluation::Disc => {
+                let n = board.getSize() * board.getSize();
+                board.setCoefs(&vec![1; n as usize]);
+            },
+            Evaluation::Weights(weights) => {
+                if !board.setCoefs(weights) {
+                    logging::debug(|| format!("{}: {} weights do not fit the board", self.getName(), weights.len()));
+                }
+            }
+        }
+        let mut report = match self.search(&board, piece) {
+            Some(report) => report,
+            None => {
+                return Some(Move::Pass);
+            }
+        };
+        if self.randomness != Randomness::None {
+            if let Some((pos, score)) = selectMove(&report.moveValues, self.randomness, &mut self.rng) {
+                if report.getBestMove() != Some(pos) {
+                    // 最善手以外を選んだので、読み筋はその手だけにする
+                    report.pv = vec!(pos);
+                    report.score = score;
+                }
+            }
+        }
+        let optPos = report.getBestMove();
+        logging::debug(|| format!("{}: {}", self.getName(), report));
+        self.lastReport = Some(report);
+        return match optPos {
+            Some(pos) => Some(Move::Put(pos)),
+            None => Some(Move::Pass)
+        };
+    }
+
+    fn getEvaluation(&self) -> Option<String> {
+        return self.lastReport.as_ref().map(|report| format!("score {}", report.score));
+    }
+
+    fn getLastReport(&self) -> Option<SearchReport> {
+        return self.lastReport.clone();
+    }
+
+    fn setTimeLeft(&mut self, remaining: Duration, increment: Duration) {
+        self.optTimeLeft = Some((remaining, increment));
+    }
+
+    fn setAbortFlag(&mut self, abort: Arc<AtomicBool>) {
+        self.abort = abort;
+    }
+
+    fn setOnProgress(&mut self, onProgress: ProgressFn) {
+        self.optOnProgress = Some(onProgress);
+    }
+}
+
+// モンテカルロ木探索で選ぶ
+pub struct MctsPlayer {
+    pub engine: Mcts,
+    optTimeLeft: Option<(Duration, Duration)>,  // 時計の残り時間と増加時間
+    abort: Arc<AtomicBool>
+}
+
+impl MctsPlayer {
+    pub fn new(limit: MctsLimit) -> Self {
+        return MctsPlayer { engine: Mcts::new(limit), optTimeLeft: None, abort: Arc::new(AtomicBool::new(false)) };
+    }
+}
+
+impl Player for MctsPlayer {
+    fn getName(&self) -> String {
+        return "mcts".to_string();
+    }
+
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move> {
+        // 時計があれば、割り当てた時間だけ探索する
+        let limit = self.engine.limit;
+        if let Some((remaining, increment)) = self.optTimeLeft {
+            let budget = allocateTime(remaining, increment, board.getEmptyCount());
+            self.engine.limit = match limit {
+                MctsLimit::Time(t) => MctsLimit::Time(t.min(budget)),
+                MctsLimit::Iterations(_) => MctsLimit::Time(budget)
+            };
+        }
+        let optPos = self.engine.searchWithAbort(board, piece, &self.abort);
+        self.engine.limit = limit;
+
+        return match optPos {
+            Some(pos) => Some(Move::Put(pos)),
+            None => Some(Move::Pass)
+        };
+    }
+
+    fn reset(&mut self) {
+        self.engine.clear();
+    }
+
+    fn setTimeLeft(&mut self, remaining: Duration, increment: Duration) {
+        self.optTimeLeft = Some((remaining, increment));
+    }
+
+    fn setAbortFlag(&mut self, abort: Arc<AtomicBool>) {
+        self.abort = abort;
+    }
+
+    fn getEvaluation(&self) -> Option<String> {
+        return self.engine.getMoveStats().first().map(|stat| format!("win {:.1}%", stat.winRate * 100.0));
+    }
+}
+
+// 一定の確率でわざと置ける場所からランダムに選ぶ（弱いレベル用）
+//
+// それ以外はinnerに打たせる．
+pub struct MistakePlayer {
+    inner: Box<dyn Player>,
+    pub mistakeRate: f64,   // ランダムに選ぶ確率（0..1）
+    rng: Rng,
+    bLastMistake: bool      // 直前の手がランダムだった
+}
+
+impl MistakePlayer {
+    pub fn new(inner: Box<dyn Player>, mistakeRate: f64, seed: u64) -> Self {
+        return MistakePlayer { inner, mistakeRate, rng: Rng::new(seed), bLastMistake: false };
+    }
+}
+
+impl Player for MistakePlayer {
+    fn getName(&self) -> String {
+        return self.inner.getName();
+    }
+
+    fn getMove(&mut self, board: &Board, piece: &Piece) -> Option<Move> {
+        self.bLastMistake = false;
+        let places = board.searchPos(piece);
+        if places.len() > 0 && self.rng.nextF64() < self.mistakeRate {
+            self.bLastMistake = true;
+            let i = self.rng.nextRange(places.len());
+            logging::debug(|| format!("{}: random move {}", self.getName(), Pos::toDesc(places[i].pos.x, places[i].pos.y)));
+            return Some(Move::Put(places[i].pos));
+        }
+        return self.inner.getMove(board, piece);
+    }
+
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+
+    fn setAbortFlag(&mut self, abort: Arc<AtomicBool>) {
+        self.inner.setAbortFlag(abort);
+    }
+
+    fn setTimeLeft(&mut self, remaining: Duration, increment: Duration) {
+        self.inner.setTimeLeft(remaining, increment);
+    }
+
+    fn setOnProgress(&mut self, onProgress: ProgressFn) {
+        self.inner.setOnProgress(onProgress);
+    }
+
+    fn getEvaluation(&self) -> Option<String> {
+        if self.bLastMistake {
+            return None;
+        }
+        return self.inner.getEvaluation();
+    }
+
+    fn getLastReport(&self) -> Option<SearchReport> {
+        if self.bLastMistake {
+            return None;
+        }
+        return self.inner.getLastReport();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+    use crate::clock::Clock;
+
+    fn startBoard() -> Board {
+        let mut board = Board::new();
+        board.init();
+        return board;
+    }
+
+    #[test]
+    fn engineReturnsWithinClock() {
+        let mut board = startBoard();
+        let mut piece = Piece::Black;
+        for desc in &["F5", "F6", "E6", "F4"] {
+            board = board.put(&piece, &Pos::fromDesc(desc).unwrap()).unwrap().board;
+            piece = Piece::getOpponent(&piece);
+        }
+
+        let mut player = EnginePlayer::new(13);
+        player.setTimeLeft(Duration::from_millis(50), Duration::from_secs(0));
+        let start = Instant::now();
+        let optMove = player.getMove(&board, &Piece::Black);
+        assert!(start.elapsed() < Duration::from_millis(50), "took {:?}", start.elapsed());
+        match optMove {
+            Some(Move::Put(pos)) => assert!(board.put(&Piece::Black, &pos).is_some()),
+            m => panic!("unexpected {:?}", m)
+        }
+    }
+
+    #[test]
+    fn engineDoesNotLoseOnTime() {
+        let mut players = [EnginePlayer::new(13), EnginePlayer::new(13)];
+        let mut clock = Clock::new(Duration::from_millis(300), Duration::from_secs(0));
+        let mut board = startBoard();
+        let mut piece = Piece::Black;
+        while board.searchPos(&piece).len() > 0 || board.searchPos(&Piece::getOpponent(&piece)).len() > 0 {
+            if board.searchPos(&piece).len() > 0 {
+                let player = &mut players[if piece == Piece::Black { 0 } else { 1 }];
+                player.setTimeLeft(clock.getRemaining(&piece), clock.increment);
+                clock.start(&piece);
+                match player.getMove(&board, &piece) {
+                    Some(Move::Put(pos)) => board = board.put(&piece, &pos).unwrap().board,
+                    m => panic!("unexpected {:?}", m)
+                }
+                assert!(clock.punch(), "{:?} lost on time", piece);
+            }
+            piece = Piece::getOpponent(&piece);
+        }
+    }
+
+    #[test]
+    fn engineReportsProgress() {
+        let (tx, rx) = std::sync::mpsc::channel();
+        let mut player = EnginePlayer::new(4);
+        player.setOnProgress(Box::new(move |report| tx.send(report.clone()).unwrap()));
+        let optMove = player.getMove(&startBoard(), &Piece::Black);
+
+        let reports = rx.try_iter().collect::<Vec<SearchReport>>();
+        assert_eq!(reports.iter().map(|r| r.depth).collect::<Vec<i32>>(), vec!(1, 2, 3, 4));
+        let last = player.getLastReport().unwrap();
+        assert_eq!(reports.last(), Some(&last));
+        assert_eq!(optMove, last.getBestMove().map(Move::Put));
+        assert!(last.nodes > reports[0].nodes);
+    }
+
+    // seedごとにengineが選んだ手
+    fn getMovesBySeed(randomness: Randomness) -> Vec<Option<Move>> {
+        // 直後のスコアが最も高い手は7手のうち1手だけの局面
+        let mut board = startBoard();
+        let mut piece = Piece::Black;
+        for desc in &["F5", "D6", "C5", "F4", "E3", "F6"] {
+            board = board.put(&piece, &Pos::fromDesc(desc).unwrap()).unwrap().board;
+            piece = Piece::getOpponent(&piece);
+        }
+        return (0..20).map(|seed| {
+            let mut player = EnginePlayer::new(3);
+            player.randomness = randomness;
+            player.setSeed(seed);
+            let optMove = player.getMove(&board, &Piece::Black);
+            let report = player.getLastReport().unwrap();
+            // すべての置ける場所を読んでいる
+            if randomness != Randomness::None {
+                // 最善でない手も読んでいる
+                assert_eq!(report.moveValues.len(), board.searchPos(&Piece::Black).len());
+            }
+            return optMove;
+        }).collect();
+    }
+
+    #[test]
+    fn engineRandomness() {
+        let fixed = getMovesBySeed(Randomness::None);
+        assert!(fixed.iter().all(|m| *m == fixed[0]));
+
+        let mut varied = getMovesBySeed(Randomness::Softmax(20.0));
+        varied.sort_by_key(|m| format!("{:?}", m));
+        varied.dedup();
+        assert!(varied.len() > 1, "{:?}", varied);
+    }
+}
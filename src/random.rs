@@ -0,0 +1,51 @@
+#![allow(non_snake_case)]
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// 乱数生成器 (xorshift64*)
+//
+// 外部クレートに依存しないための簡易なもの．
+// 同じseedを与えれば同じ乱数列が得られる．
+#[derive(Clone, Debug, PartialEq)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // 状態が0だと0しか出てこないので避ける
+        let state = if seed == 0 { 0x9E37_79B9_7F4A_7C15 } else { seed };
+        return Rng { state };
+    }
+
+    // 現在時刻をseedにする
+    pub fn fromTime() -> Self {
+        let nanos = match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(d) => d.as_nanos() as u64,
+            Err(_) => 0
+        };
+        return Rng::new(nanos);
+    }
+
+    pub fn nextU64(&mut self) -> u64 {
+        let mut x = self.state;
+        x ^= x >> 12;
+        x ^= x << 25;
+        x ^= x >> 27;
+        self.state = x;
+        return x.wrapping_mul(0x2545_F491_4F6C_DD1D);
+    }
+
+    // 0..nの範囲の整数を返す（n==0のときは0）
+    pub fn nextRange(&mut self, n: usize) -> usize {
+        if n == 0 {
+            return 0;
+        }
+        return (self.nextU64() % (n as u64)) as usize;
+    }
+
+    // 0.0以上1.0未満の実数を返す
+    pub fn nextF64(&mut self) -> f64 {
+        return (self.nextU64() >> 11) as f64 / ((1u64 << 53) as f64);
+    }
+}
@@ -0,0 +1,265 @@
+#![allow(non_snake_case)]
+
+use std::fs;
+
+use crate::board::{isValidSize, Board, Piece, Pos, SearchResult2};
+
+// 棋譜の1手
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RecordMove {
+    pub piece: Piece,       // 打った側
+    pub pos: Option<Pos>    // 置いた位置（Noneはパス）
+}
+
+// 棋譜を再生したときの各局面
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReplayStep {
+    pub board: Board,                       // この局面の盤
+    pub turn: Piece,                        // この局面で次に打つ側
+    pub optResult: Option<SearchResult2>    // この局面に至った手の結果（開始局面とパスはNone）
+}
+
+// 棋譜
+//
+// ファイルは次の形式のテキストで保存する．盤は黒がX、白がO、空白が-．
+// 盤の大きさは1行の文字数で表す．
+//
+// [Board]
+// --------
+// ...（8x8なら8行）
+// [First] Black
+// [Moves]
+// F5 D6 C3 pass ...
+#[derive(Clone, Debug, PartialEq)]
+pub struct GameRecord {
+    pub startBoard: Board,      // 開始局面
+    pub firstPiece: Piece,      // 先手
+    pub moves: Vec<RecordMove>
+}
+
+impl GameRecord {
+    pub fn new(startBoard: &Board, firstPiece: &Piece) -> Self {
+        return GameRecord {
+            startBoard: startBoard.clone(),
+            firstPiece: *firstPiece,
+            moves: vec!()
+        };
+    }
+
+    pub fn push(&mut self, piece: &Piece, pos: Option<Pos>) {
+        self.moves.push(RecordMove { piece: *piece, pos });
+    }
+
+    // 手順を最初から再生し、開始局面を含む各局面を返す
+    //
+    // @return 置けない位置があった場合はErr
+    pub fn replay(&self) -> Result<Vec<ReplayStep>, String> {
+        let mut steps = vec!(ReplayStep {
+            board: self.startBoard.clone(),
+            turn: self.firstPiece,
+            optResult: None
+        });
+
+        let mut board = self.startBoard.clone();
+        for (i, m) in self.moves.iter().enumerate() {
+            let mut optResult = None;
+            if let Some(pos) = &m.pos {
+                match board.put(&m.piece, pos) {
+                    Some(ret) => {
+                        board = ret.board.clone();
+                        optResult = Some(ret);
+                    },
+                    None => {
+                        return Err(format!("move {} ({}) is illegal", i + 1, Pos::toDesc(pos.x, pos.y)));
+                    }
+                }
+            }
+            steps.push(ReplayStep {
+                board: board.clone(),
+                turn: Piece::getOpponent(&m.piece),
+                optResult
+            });
+        }
+
+        return Ok(steps);
+    }
+
+    // 手の文字列（"F5"またはパスの"pass"）
+    pub fn getMoveDesc(m: &RecordMove) -> String {
+        return match &m.pos {
+            Some(pos) => Pos::toDesc(pos.x, pos.y),
+            None => "pass".to_string()
+        };
+    }
+
+    // ファイルに書く形式の文字列にする
+    pub fn format(&self) -> String {
+        let mut text = String::from("[Board]\n");
+        let size = self.startBoard.getSize();
+        for y in 1..=size {
+            for x in 1..=size {
+                text.push(match self.startBoard.getPiece(x, y) {
+                    Some(Piece::Black) => 'X',
+                    Some(Piece::White) => 'O',
+                    _ => '-'
+                });
+            }
+            text.push('\n');
+        }
+        text.push_str(&format!("[First] {}\n", pieceToName(&self.firstPiece)));
+        text.push_str("[Moves]\n");
+        let moves = self.moves.iter().map(GameRecord::getMoveDesc).collect::<Vec<String>>();
+        for chunk in moves.chunks(10) {
+            text.push_str(&chunk.join(" "));
+            text.push('\n');
+        }
+        return text;
+    }
+
+    // formatの形式の文字列を読む
+    //
+    // 打った側は先手から交互とし、passで手番が入れ替わる．
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let lines = text.lines().map(|l| l.trim()).collect::<Vec<&str>>();
+
+        let boardIdx = lines.iter().position(|&l| l == "[Board]").ok_or("[Board] not found")?;
+        let size = lines.get(boardIdx + 1).ok_or("board is too short")?.chars().count();
+        if !isValidSize(size as i32) {
+            return Err(format!("unsupported board size {}", size));
+        }
+        let mut board = Board::withSize(size as i32);
+        for y in 1..=size {
+            let line = lines.get(boardIdx + y).ok_or("board is too short")?.chars().collect::<Vec<char>>();
+            if line.len() < size {
+                return Err(format!("board line {} is too short", y));
+            }
+            for x in 1..=size {
+                let piece = match line[x - 1] {
+                    'X' | 'x' => Piece::Black,
+                    'O' | 'o' => Piece::White,
+                    '-' | '.' => Piece::Space,
+                    c => {
+                        return Err(format!("invalid character '{}' in board", c));
+                    }
+                };
+                board.setPiece(x as i32, y as i32, piece);
+            }
+        }
+
+        let mut firstPiece = Piece::Black;
+        if let Some(line) = lines.iter().find(|l| l.starts_with("[First]")) {
+            firstPiece = match line["[First]".len()..].trim() {
+                "Black" => Piece::Black,
+                "White" => Piece::White,
+                s => {
+                    return Err(format!("invalid first player '{}'", s));
+                }
+            };
+        }
+
+        let mut record = GameRecord::new(&board, &firstPiece);
+        if let Some(movesIdx) = lines.iter().position(|&l| l == "[Moves]") {
+            let mut piece = firstPiece;
+            for line in &lines[movesIdx + 1..] {
+                if line.starts_with('[') {
+                    break;
+                }
+                for token in line.split_whitespace() {
+                    if token.eq_ignore_ascii_case("pass") {
+                        record.push(&piece, None);
+                    } else {
+                        match Pos::fromDesc(token) {
+                            Some(pos) => record.push(&piece, Some(pos)),
+                            None => {
+                                return Err(format!("invalid move '{}'", token));
+                            }
+                        }
+                    }
+                    piece = Piece::getOpponent(&piece);
+                }
+            }
+        }
+
+        return Ok(record);
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        return fs::write(path, self.format()).map_err(|e| format!("{}: {}", path, e));
+    }
+
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        return GameRecord::parse(&text);
+    }
+}
+
+pub fn pieceToName(piece: &Piece) -> &str {
+    return match piece {
+        Piece::Black => "Black",
+        Piece::White => "White",
+        Piece::Space => "Space"
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn createRecord(size: i32, moves: &[&str]) -> GameRecord {
+        let mut board = Board::withSize(size);
+        board.init();
+        let mut record = GameRecord::new(&board, &Piece::Black);
+        let mut piece = Piece::Black;
+        for desc in moves {
+            record.push(&piece, Pos::fromDesc(desc));
+            piece = Piece::getOpponent(&piece);
+        }
+        return record;
+    }
+
+    #[test]
+    fn formatAndParse() {
+        let record = createRecord(8, &["F5", "D6", "C3", "D3", "C4", "F4", "F6", "F3", "E6", "E7", "D7"]);
+        let text = record.format();
+        assert!(text.starts_with("[Board]\n--------\n"));
+        assert!(text.contains("[First] Black\n[Moves]\nF5 D6 C3 D3 C4 F4 F6 F3 E6 E7\nD7\n"));
+        assert_eq!(GameRecord::parse(&text), Ok(record));
+    }
+
+    #[test]
+    fn formatAndParseWithPass() {
+        let mut record = createRecord(6, &["B3", "B2"]);
+        record.push(&Piece::Black, None);
+        record.firstPiece = Piece::White;
+        let parsed = GameRecord::parse(&record.format()).unwrap();
+        assert_eq!(parsed.startBoard.getSize(), 6);
+        assert_eq!(parsed.firstPiece, Piece::White);
+        assert_eq!(parsed.moves.iter().map(GameRecord::getMoveDesc).collect::<Vec<String>>(), vec!("B3", "B2", "pass"));
+        // 打った側は先手から交互になる
+        assert_eq!(parsed.moves[0].piece, Piece::White);
+        assert_eq!(parsed.moves[2].piece, Piece::White);
+    }
+
+    #[test]
+    fn replay() {
+        let record = createRecord(8, &["F5", "D6"]);
+        let steps = record.replay().unwrap();
+        assert_eq!(steps.len(), 3);
+        assert_eq!(steps[0].optResult, None);
+        assert_eq!(steps[1].turn, Piece::White);
+        assert_eq!(steps[2].turn, Piece::Black);
+        assert_eq!(steps[2].board.getPiece(4, 6), Some(&Piece::White));
+
+        let illegal = createRecord(8, &["F5", "A1"]);
+        assert_eq!(illegal.replay(), Err("move 2 (A1) is illegal".to_string()));
+    }
+
+    #[test]
+    fn parseErrors() {
+        assert!(GameRecord::parse("[Moves]\nF5\n").is_err());
+        assert!(GameRecord::parse("[Board]\n---\n---\n---\n").is_err());
+        assert!(GameRecord::parse("[Board]\n----\n--X-\n-OZ-\n----\n").is_err());
+        let text = createRecord(8, &["F5"]).format().replace("F5", "Z9");
+        assert_eq!(GameRecord::parse(&text), Err("invalid move 'Z9'".to_string()));
+    }
+}
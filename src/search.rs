@@ -0,0 +1,297 @@
+#![allow(non_snake_case)]
+
+use std::fmt;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+use crate::board::{Board, Piece, Pos, SearchResult3, SearchResult3Sub, SearchStop};
+
+// 探索の結果と統計
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchReport {
+    pub depth: i32,                 // 読んだ深さ
+    pub selDepth: i32,              // 実際に読んだ最も長い手順の手数（終局があれば短くなる）
+    pub nodes: u64,                 // 生成した局面の数
+    pub elapsed: Duration,          // かかった時間
+    pub score: i32,                 // 最善手のスコア
+    pub pv: Vec<Pos>,               // 最善手から始まる読み筋（パスは含まない）
+    pub moveValues: Vec<(Pos, i32)>,// 置ける場所ごとのスコア
+    pub bAborted: bool              // 途中で打ち切られたらtrue（結果は不完全）
+}
+
+impl SearchReport {
+    // 1秒あたりの局面数
+    pub fn getNps(&self) -> u64 {
+        let secs = self.elapsed.as_secs_f64();
+        if secs <= 0.0 {
+            return self.nodes;
+        }
+        return (self.nodes as f64 / secs) as u64;
+    }
+
+    // 最善手（置けない場合はNone）
+    pub fn getBestMove(&self) -> Option<Pos> {
+        return self.pv.first().cloned();
+    }
+}
+
+impl fmt::Display for SearchReport {
+    // (ex)
+    // depth 4/4 nodes 1234 nps 56789 time 0.021 score 30 pv F5 D6 C3 D3
+    // F5:30 D6:12 C4:-3
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "depth {}/{} nodes {} nps {} time {:.3} score {} pv {}",
+            self.depth, self.selDepth, self.nodes, self.getNps(), self.elapsed.as_secs_f64(), self.score, formatPv(&self.pv))?;
+        if self.bAborted {
+            write!(f, " (aborted)")?;
+        }
+        let mut values = self.moveValues.clone();
+        values.sort_by(|a, b| b.1.cmp(&a.1));
+        let values = values.iter().map(|(pos, score)| format!("{}:{}", Pos::toDesc(pos.x, pos.y), score)).collect::<Vec<String>>();
+        return write!(f, "\n{}", values.join(" "));
+    }
+}
+
+// 読み筋を"F5 D6 C3"の形式にする
+pub fn formatPv(pv: &[Pos]) -> String {
+    return pv.iter().map(|pos| Pos::toDesc(pos.x, pos.y)).collect::<Vec<String>>().join(" ");
+}
+
+// pieceの置ける場所ごとにdepth手先まで読み、結果をまとめる
+//
+// スコアの意味はgetBestMoveと同じ（値が大きいほどpieceに有利）．
+// 同点なら先に見つかった手を最善手とする．
+pub fn search(board: &Board, piece: &Piece, depth: i32, stop: &SearchStop) -> SearchReport {
+    let start = Instant::now();
+    let mut report = SearchReport {
+        depth,
+        selDepth: 0,
+        nodes: 0,
+        elapsed: Duration::from_secs(0),
+        score: 0,
+        pv: vec!(),
+        moveValues: vec!(),
+        bAborted: false
+    };
+
+    let nextBoards = board.genNextBoards(piece);
+    report.nodes += nextBoards.len() as u64;
+    let mut optBest: Option<SearchResult3> = None;
+    for nextBoard in &nextBoards {
+        let tree = SearchResult3 {
+            path: vec!(SearchResult3Sub {
+                pos: nextBoard.pos,
+                piece: *piece
+            }),
+            board: nextBoard.board.clone(),
+            ntake: nextBoard.ntake,
+            score: nextBoard.score
+        };
+
+        let mut line = tree.clone();
+        if depth > 1 {
+            let childResults = nextBoard.board.genSearchTreeSubWithStats(piece, &Piece::getOpponent(piece), depth - 1, &tree, stop, &mut report.nodes);
+            for child in &childResults {
+                report.selDepth = report.selDepth.max(child.path.len() as i32);
+            }
+            if let Some(best) = childResults.iter().max_by_key(|&elem| elem.score) {
+                line = best.clone();
+            }
+        }
+        report.selDepth = report.selDepth.max(line.path.len() as i32);
+        report.moveValues.push((nextBoard.pos, line.score));
+
+        let bBetter = match &optBest {
+            Some(best) => line.score > best.score,
+            None => true
+        };
+        if bBetter {
+            optBest = Some(line);
+        }
+    }
+
+    if let Some(best) = optBest {
+        report.score = best.score;
+        report.pv = best.path.iter().map(|sub| sub.pos).collect();
+    }
+    report.bAborted = stop.isStopped();
+    report.elapsed = start.elapsed();
+    return report;
+}
+
+// getBestMoveと同じ探索をし、結果をまとめる
+//
+// getBestMoveは直後のスコアが最も高い手しか読まないので、
+// moveValuesにはそれらの手のスコアだけが入る．
+pub fn searchBestMove(board: &Board, piece: &Piece, depth: i32, stop: &SearchStop) -> SearchReport {
+    let start = Instant::now();
+    let mut nodes = 0;
+    let root = SearchResult3 {
+        path: vec!(),
+        board: board.clone(),
+        ntake: 0,
+        score: 0
+    };
+    let allMoves = board.genSearchTreeSubWithStats(piece, piece, depth, &root, stop, &mut nodes);
+
+    let mut optBest: Option<&SearchResult3> = None;
+    let mut moveValues: Vec<(Pos, i32)> = vec!();
+    for m in &allMoves {
+        if m.path.is_empty() {
+            // 置ける場所がなかった
+            continue;
+        }
+        let pos = m.path[0].pos;
+        match moveValues.iter_mut().find(|v| v.0 == pos) {
+            Some(v) => v.1 = v.1.max(m.score),
+            None => moveValues.push((pos, m.score))
+        }
+        let bBetter = match optBest {
+            Some(best) => m.score > best.score,
+            None => true
+        };
+        if bBetter {
+            optBest = Some(m);
+        }
+    }
+
+    return SearchReport {
+        depth,
+        selDepth: allMoves.iter().map(|m| m.path.len() as i32).max().unwrap_or(0),
+        nodes,
+        elapsed: start.elapsed(),
+        score: optBest.map_or(0, |best| best.score),
+        pv: optBest.map_or(vec!(), |best| best.path.iter().map(|sub| sub.pos).collect()),
+        moveValues,
+        bAborted: stop.isStopped()
+    };
+}
+
+// 1手ずつ深く読み直し（反復深化）、深さごとの結果をonProgressで知らせる
+//
+// 深さごとの探索はsearchDepth（searchかsearchBestMove）で行う．
+// maxDepthまで読むか、optTimeを使い切りそうになるか、abortがtrueになったらやめる．
+// optTimeを過ぎたら読んでいる途中でも打ち切り、その深さの結果は使わない．
+// ただし深さ1はすぐ終わるので時間に関係なく読み切る．
+// 結果のnodesとelapsedは深さ1からの合計．
+//
+// @return 最後に読み切った深さの結果（置ける場所がない、または深さ1の途中で中断されたらNone）
+pub fn iterativeSearch(
+    board: &Board,
+    piece: &Piece,
+    maxDepth: i32,
+    optTime: Option<Duration>,
+    searchDepth: fn(&Board, &Piece, i32, &SearchStop) -> SearchReport,
+    abort: &AtomicBool,
+    onProgress: &mut dyn FnMut(&SearchReport)
+) -> Option<SearchReport> {
+    let start = Instant::now();
+    let deadline = SearchStop::withDeadline(abort, optTime.map(|time| start + time));
+    let mut optBest = None;
+    let mut nodes = 0;
+    for depth in 1..=maxDepth {
+        let stop = if depth == 1 { SearchStop::new(abort) } else { deadline };
+        let mut report = searchDepth(board, piece, depth, &stop);
+        if report.moveValues.len() == 0 || report.bAborted {
+            break;
+        }
+        // 局面の数と時間はそれまでの深さの分も含める
+        nodes += report.nodes;
+        report.nodes = nodes;
+        report.elapsed = start.elapsed();
+        onProgress(&report);
+        optBest = Some(report);
+
+        // 次の深さは少なくとも今の数倍かかるので、残りが少なければやめる
+        if let Some(time) = optTime {
+            if start.elapsed() * 4 > time {
+                break;
+            }
+        }
+    }
+    return optBest;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // F5 F6 E6 F4 E3まで打った局面（白の手番）
+    fn createBoard() -> Board {
+        let mut board = Board::new();
+        board.init();
+        let mut piece = Piece::Black;
+        for desc in &["F5", "F6", "E6", "F4", "E3"] {
+            board = board.put(&piece, &Pos::fromDesc(desc).unwrap()).unwrap().board;
+            piece = Piece::getOpponent(&piece);
+        }
+        return board;
+    }
+
+    #[test]
+    fn searchAllMoves() {
+        let board = createBoard();
+        let report = search(&board, &Piece::White, 4, &SearchStop::new(&AtomicBool::new(false)));
+        let mut legal = board.searchPos(&Piece::White).iter().map(|r| Pos::toDesc(r.pos.x, r.pos.y)).collect::<Vec<String>>();
+        let mut searched = report.moveValues.iter().map(|v| Pos::toDesc(v.0.x, v.0.y)).collect::<Vec<String>>();
+        legal.sort();
+        searched.sort();
+        assert_eq!(searched, legal);
+
+        let best = report.getBestMove().unwrap();
+        assert_eq!(report.moveValues.iter().map(|v| v.1).max(), Some(report.score));
+        assert!(report.moveValues.contains(&(best, report.score)));
+        assert!(report.pv.len() as i32 <= report.depth);
+        assert!(report.selDepth >= report.pv.len() as i32);
+        assert!(report.nodes > 0);
+        assert!(!report.bAborted);
+    }
+
+    #[test]
+    fn searchBestMoveAgreesWithBoard() {
+        let board = createBoard();
+        for depth in 1..=5 {
+            let report = searchBestMove(&board, &Piece::White, depth, &SearchStop::new(&AtomicBool::new(false)));
+            let best = board.getBestMove(&Piece::White, depth).unwrap();
+            assert_eq!(report.pv, best.path.iter().map(|sub| sub.pos).collect::<Vec<Pos>>());
+            assert_eq!(report.score, best.score);
+            assert!(report.moveValues.contains(&(report.getBestMove().unwrap(), report.score)));
+            for (pos, _) in &report.moveValues {
+                assert!(board.put(&Piece::White, pos).is_some());
+            }
+            assert!(report.nodes > 0);
+        }
+    }
+
+    #[test]
+    fn searchWithoutMoves() {
+        let board = Board::new();
+        let report = search(&board, &Piece::Black, 3, &SearchStop::new(&AtomicBool::new(false)));
+        assert_eq!(report.getBestMove(), None);
+        assert_eq!(report.moveValues, vec!());
+        assert_eq!(iterativeSearch(&board, &Piece::Black, 3, None, searchBestMove, &AtomicBool::new(false), &mut |_| {}), None);
+    }
+
+    #[test]
+    fn iterativeSearchStopsAtDeadline() {
+        let mut board = Board::new();
+        board.init();
+        let time = Duration::from_millis(30);
+        let start = Instant::now();
+        let mut depths = vec!();
+        let report = iterativeSearch(&board, &Piece::Black, 60, Some(time), search, &AtomicBool::new(false), &mut |r| depths.push(r.depth)).unwrap();
+        assert!(start.elapsed() < time + Duration::from_millis(20), "took {:?}", start.elapsed());
+        // 打ち切った深さの結果は使わない
+        assert!(!report.bAborted);
+        assert_eq!(Some(&report.depth), depths.last());
+        assert_eq!(depths, (1..=report.depth).collect::<Vec<i32>>());
+    }
+
+    #[test]
+    fn iterativeSearchAborted() {
+        let mut board = Board::new();
+        board.init();
+        let report = iterativeSearch(&board, &Piece::Black, 60, None, searchBestMove, &AtomicBool::new(true), &mut |_| {});
+        assert_eq!(report, None);
+    }
+}
@@ -0,0 +1,155 @@
+#![allow(non_snake_case)]
+
+use std::path::{Path, PathBuf};
+
+use sdl2::pixels::Color;
+
+// 画面の見た目の設定
+//
+// 設定ファイルは1行に1つ「key = value」の形式で書く．#以降はコメント．
+// 色は「r,g,b」または「r,g,b,a」で指定する．
+//
+// (ex)
+// sprites = assets/Image1.png
+// font = assets/arial.ttf
+// board = 0,128,64
+// labels = false
+#[derive(Clone, Debug, PartialEq)]
+pub struct Theme {
+    pub sprites: String,        // 駒の画像（空白、黒、白の順に96x96で横に並べたもの）
+    pub font: String,           // フォント
+    pub background: Color,      // 背景色
+    pub boardColor: Color,      // 盤の色（画像がない場合）
+    pub gridColor: Color,       // マスの線の色（画像がない場合）
+    pub blackColor: Color,      // 黒の駒の色（画像がない場合）
+    pub whiteColor: Color,      // 白の駒の色（画像がない場合）
+    pub textColor: Color,       // 文字の色
+    pub messageColor: Color,    // 知らせの文字の色
+    pub hintColor: Color,       // 置ける場所の印の色
+    pub lastMoveColor: Color,   // 最後に打たれた場所の枠の色
+    pub cursorColor: Color,     // キーボードのカーソルの色
+    pub showLabels: bool        // 盤の周りに座標(A-H, 1-8)を表示する
+}
+
+impl Theme {
+    pub fn new() -> Self {
+        return Theme {
+            sprites: "assets/Image1.png".to_string(),
+            font: "assets/arial.ttf".to_string(),
+            background: Color::RGB(0, 0, 0),
+            boardColor: Color::RGB(0, 128, 64),
+            gridColor: Color::RGB(0, 64, 32),
+            blackColor: Color::RGB(16, 16, 16),
+            whiteColor: Color::RGB(240, 240, 240),
+            textColor: Color::RGB(255, 255, 255),
+            messageColor: Color::RGB(255, 255, 0),
+            hintColor: Color::RGBA(255, 255, 0, 96),
+            lastMoveColor: Color::RGB(255, 0, 0),
+            cursorColor: Color::RGB(0, 192, 255),
+            showLabels: true
+        };
+    }
+
+    // 設定ファイルを読み込む．書かれていない項目は既定値のまま
+    pub fn load(path: &str) -> Result<Self, String> {
+        let text = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path, e))?;
+        let mut theme = Theme::new();
+
+        for (i, rawLine) in text.lines().enumerate() {
+            let line = match rawLine.find('#') {
+                Some(n) => &rawLine[..n],
+                None => rawLine
+            }.trim();
+            if line.len() == 0 {
+                continue;
+            }
+            let mut it = line.splitn(2, '=');
+            let key = it.next().unwrap_or("").trim();
+            let value = it.next().unwrap_or("").trim();
+            if let Err(e) = theme.set(key, value) {
+                return Err(format!("{}:{}: {}", path, i + 1, e));
+            }
+        }
+
+        return Ok(theme);
+    }
+
+    // 項目を1つ設定する
+    pub fn set(&mut self, key: &str, value: &str) -> Result<(), String> {
+        match key {
+            "sprites" => self.sprites = value.to_string(),
+            "font" => self.font = value.to_string(),
+            "background" => self.background = parseColor(value)?,
+            "board" => self.boardColor = parseColor(value)?,
+            "grid" => self.gridColor = parseColor(value)?,
+            "black" => self.blackColor = parseColor(value)?,
+            "white" => self.whiteColor = parseColor(value)?,
+            "text" => self.textColor = parseColor(value)?,
+            "message" => self.messageColor = parseColor(value)?,
+            "hint" => self.hintColor = parseColor(value)?,
+            "lastmove" => self.lastMoveColor = parseColor(value)?,
+            "cursor" => self.cursorColor = parseColor(value)?,
+            "labels" => {
+                self.showLabels = match value {
+                    "true" | "on" | "yes" => true,
+                    "false" | "off" | "no" => false,
+                    _ => {
+                        return Err(format!("invalid boolean '{}'", value));
+                    }
+                };
+            },
+            _ => {
+                return Err(format!("unknown key '{}'", key));
+            }
+        }
+        return Ok(());
+    }
+}
+
+// 「r,g,b」または「r,g,b,a」を色にする
+fn parseColor(value: &str) -> Result<Color, String> {
+    let mut c = vec!();
+    for s in value.split(',') {
+        match s.trim().parse::<u8>() {
+            Ok(n) => c.push(n),
+            Err(_) => {
+                return Err(format!("invalid color '{}'", value));
+            }
+        }
+    }
+    return match c.len() {
+        3 => Ok(Color::RGB(c[0], c[1], c[2])),
+        4 => Ok(Color::RGBA(c[0], c[1], c[2], c[3])),
+        _ => Err(format!("invalid color '{}'", value))
+    };
+}
+
+// 画像やフォントなどのファイルを探す
+//
+// 絶対パスならそのまま使う．相対パスなら実行ファイルのあるディレクトリ
+// （とその上のディレクトリ）、カレントディレクトリの順に探す．
+// cargo runで実行した場合はtarget/debugの2つ上にassetsがある．
+pub fn findAsset(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.is_absolute() {
+        return if path.exists() { Some(path.to_path_buf()) } else { None };
+    }
+
+    if let Ok(exe) = std::env::current_exe() {
+        let mut optDir = exe.parent();
+        for _ in 0..3 {
+            if let Some(dir) = optDir {
+                let candidate = dir.join(path);
+                if candidate.exists() {
+                    return Some(candidate);
+                }
+                optDir = dir.parent();
+            }
+        }
+    }
+
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    return None;
+}